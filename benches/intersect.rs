@@ -0,0 +1,104 @@
+//! Times the ray/polygon intersection hot path and counts heap allocations
+//! made while running it. Run with `cargo bench --bench intersect`.
+
+extern crate ascworld;
+
+use ascworld::*;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAlloc;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+// small LCG so runs are reproducible without extra dependencies
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005)
+                       .wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+fn mesh(rng: &mut Lcg, n: usize) -> Vec<Polygon> {
+    (0..n).map(|_| {
+        let (x, y, z) = (rng.next() * 20., rng.next() * 20., -rng.next() * 20.);
+        Polygon::Triangle(
+            Point3::new(x, y, z),
+            Point3::new(x + rng.next(), y + rng.next(), z),
+            Point3::new(x, y + rng.next(), z - rng.next()),
+        )
+    }).collect()
+}
+
+fn rays(rng: &mut Lcg, n: usize) -> Vec<Ray3> {
+    (0..n).map(|_| Ray3::new(
+        Point3::new(rng.next() * 20., rng.next() * 20., 10.),
+        Vec3::new(rng.next() - 0.5, rng.next() - 0.5, -1.),
+    )).collect()
+}
+
+fn bench<F: FnMut() -> usize>(name: &str, iters: usize, mut f: F) {
+    let before = ALLOCS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut hits = 0;
+    for _ in 0..iters {
+        hits += f();
+    }
+    let elapsed = start.elapsed();
+    let allocs = ALLOCS.load(Ordering::Relaxed) - before;
+
+    println!("{:<24} {:>10.1} ns/iter {:>8} hits {:>6} allocs",
+             name,
+             elapsed.as_secs_f64() * 1e9 / iters as f64,
+             hits,
+             allocs);
+    assert_eq!(allocs, 0, "{} allocated in the hot path", name);
+}
+
+fn main() {
+    let mut rng = Lcg(12345);
+    let polygons = mesh(&mut rng, 2000);
+    let rays = rays(&mut rng, 1000);
+
+    bench("Polygon::intersect", 20, || {
+        rays.iter().map(|r|
+            polygons.iter().filter(|p| black_box(p.intersect(r)).is_some()).count()
+        ).sum()
+    });
+
+    let object = Object::new(Rgb(255, 255, 255), mesh(&mut rng, 2000));
+    bench("Object::intersect (bvh)", 200, || {
+        rays.iter().filter(|r| object.intersect(r).is_some()).count()
+    });
+
+    let mut t = Transform::id();
+    bench("Transform::apply", 1_000_000, || {
+        black_box(t.apply(&Transform::rotate(0.01, Vec3::new(0., 1., 0.))));
+        0
+    });
+
+    let camera = Camera::default();
+    bench("Camera::get_ray", 1000, || {
+        (0..64).filter_map(|x| camera.get_ray((x, x))).count()
+    });
+}
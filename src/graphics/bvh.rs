@@ -0,0 +1,350 @@
+//! Bounding volume hierarchy over anything that has an axis-aligned box.
+//!
+//! The tree only stores indices into the caller's slice, so the same
+//! structure is used both for the objects of a `Scene` and for the polygons
+//! of an `Object`. Items with infinite bounds, like planes, are kept out of
+//! the tree and tested against every ray.
+
+use std::f64;
+
+use super::*;
+
+// leaves with at most this many items are never split
+const MAX_LEAF: usize = 4;
+// cost of visiting a node relative to one item intersection test
+const TRAVERSAL_COST: f64 = 1.;
+// deeper nodes are forced to be leaves so traversal fits a fixed stack
+const MAX_DEPTH: usize = 62;
+const STACK_SIZE: usize = MAX_DEPTH + 2;
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    Leaf { first: usize, count: usize },
+    Inner { left: usize, right: usize },
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: [Point3; 2],
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    indices: Vec<usize>,
+    /// Items that aren't in the tree as their bounds aren't finite.
+    unbounded: Vec<usize>,
+    /// Bounds of all the items, including the unbounded ones.
+    bounds: Option<[Point3; 2]>,
+}
+
+#[inline]
+fn is_finite(b: &[Point3; 2]) -> bool {
+    let (x0, y0, z0) = b[0].unpack();
+    let (x1, y1, z1) = b[1].unpack();
+    [x0, y0, z0, x1, y1, z1].iter().all(|x| x.is_finite())
+}
+
+#[inline]
+fn union(a: &[Point3; 2], b: &[Point3; 2]) -> [Point3; 2] {
+    [
+        a[0].lower_bound(b[0]),
+        a[1].upper_bound(b[1]),
+    ]
+}
+
+#[inline]
+fn surface_area(b: &[Point3; 2]) -> f64 {
+    let (x, y, z) = (b[1] - b[0]).unpack();
+    x*y + y*z + z*x
+}
+
+#[inline]
+fn centroid(b: &[Point3; 2], axis: u8) -> f64 {
+    unsafe { b[0].uget(axis) + b[1].uget(axis) }
+}
+
+impl Bvh {
+    pub fn new() -> Self {
+        Bvh {
+            nodes: Vec::new(),
+            indices: Vec::new(),
+            unbounded: Vec::new(),
+            bounds: None,
+        }
+    }
+
+    /// Builds a tree over `bounds` using the surface area heuristic.
+    pub fn build(bounds: &[[Point3; 2]]) -> Self {
+        let (indices, unbounded) = (0..bounds.len())
+            .partition::<Vec<_>, _>(|&i| is_finite(&bounds[i]));
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(indices.len() * 2),
+            indices,
+            unbounded,
+            bounds: None,
+        };
+        if !bvh.indices.is_empty() {
+            bvh.build_node(bounds, 0, bvh.indices.len(), 0);
+        }
+        bvh.bounds = Self::total(bounds);
+        bvh
+    }
+
+    fn total(bounds: &[[Point3; 2]]) -> Option<[Point3; 2]> {
+        let mut items = bounds.iter();
+        let acc = *items.next()?;
+        Some(items.fold(acc, |a, x| union(&a, x)))
+    }
+
+    /// Number of items the tree was built over.
+    pub fn len(&self) -> usize {
+        self.indices.len() + self.unbounded.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bounds of all the items, `None` when empty. Infinite along some axes
+    /// when there are unbounded items.
+    pub fn bounds(&self) -> Option<[Point3; 2]> {
+        self.bounds
+    }
+
+    /// Recomputes node bounds bottom-up after the items moved, keeping the
+    /// tree topology. `bounds` must have the same length as at build time
+    /// and the same items must be unbounded.
+    pub fn refit(&mut self, bounds: &[[Point3; 2]]) {
+        assert_eq!(bounds.len(), self.len(), "bvh refit item count");
+        self.bounds = Self::total(bounds);
+
+        // children always come after their parent, so a reverse sweep
+        // sees every child before the node that contains it
+        for i in (0..self.nodes.len()).rev() {
+            let b = match self.nodes[i].kind {
+                NodeKind::Leaf { first, count } => {
+                    let mut items = self.indices[first..first+count].iter()
+                        .map(|&j| &bounds[j]);
+                    let acc = *items.next().unwrap();
+                    items.fold(acc, |a, x| union(&a, x))
+                },
+                NodeKind::Inner { left, right } =>
+                    union(&self.nodes[left].bounds, &self.nodes[right].bounds),
+            };
+            self.nodes[i].bounds = b;
+        }
+    }
+
+    /// Refits when the items are the same as far as the tree goes, rebuilds
+    /// when some were added, removed, or became bounded or unbounded.
+    pub fn update(&mut self, bounds: &[[Point3; 2]]) {
+        let same = bounds.len() == self.len()
+            && self.unbounded.iter().all(|&i| !is_finite(&bounds[i]))
+            && bounds.iter().filter(|b| !is_finite(b)).count() == self.unbounded.len();
+        if same {
+            self.refit(bounds);
+        } else {
+            *self = Self::build(bounds);
+        }
+    }
+
+    fn build_node(
+        &mut self,
+        bounds: &[[Point3; 2]],
+        first: usize,
+        count: usize,
+        depth: usize,
+    ) -> usize {
+        let node_bounds = {
+            let mut items = self.indices[first..first+count].iter()
+                .map(|&j| &bounds[j]);
+            let acc = *items.next().unwrap();
+            items.fold(acc, |a, x| union(&a, x))
+        };
+        let area = surface_area(&node_bounds);
+
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            bounds: node_bounds,
+            kind: NodeKind::Leaf { first, count },
+        });
+
+        if count <= MAX_LEAF || depth >= MAX_DEPTH {
+            return id;
+        }
+
+        if let Some(split) = self.sah_split(bounds, first, count, area) {
+            let left = self.build_node(bounds, first, split, depth + 1);
+            let right = self.build_node(bounds, first + split,
+                                        count - split, depth + 1);
+            self.nodes[id].kind = NodeKind::Inner { left, right };
+        }
+        id
+    }
+
+    /// Finds the cheapest split of `indices[first..first+count]` along any
+    /// axis, leaving the slice sorted along that axis. Returns the size of
+    /// the left half, or `None` if a leaf is cheaper.
+    fn sah_split(
+        &mut self,
+        bounds: &[[Point3; 2]],
+        first: usize,
+        count: usize,
+        area: f64,
+    ) -> Option<usize> {
+        let mut best: Option<(u8, usize, f64)> = None;
+        let mut right_areas = vec![0.; count];
+
+        for axis in 0..3 {
+            let items = &mut self.indices[first..first+count];
+            items.sort_by(|&a, &b| {
+                centroid(&bounds[a], axis)
+                    .partial_cmp(&centroid(&bounds[b], axis))
+                    .unwrap_or(::std::cmp::Ordering::Equal)
+            });
+
+            let mut acc = bounds[items[count-1]];
+            for i in (1..count).rev() {
+                acc = union(&acc, &bounds[items[i]]);
+                right_areas[i] = surface_area(&acc);
+            }
+
+            let mut acc = bounds[items[0]];
+            for i in 1..count {
+                let cost = surface_area(&acc) * i as f64
+                         + right_areas[i] * (count - i) as f64;
+                if best.as_ref().is_none_or(|b| cost < b.2) {
+                    best = Some((axis, i, cost));
+                }
+                acc = union(&acc, &bounds[items[i]]);
+            }
+        }
+
+        let (axis, split, cost) = best?;
+        // costs above are not normalised by the parent area
+        if cost >= area * (count as f64 - TRAVERSAL_COST) {
+            return None;
+        }
+
+        if axis != 2 {
+            self.indices[first..first+count].sort_by(|&a, &b| {
+                centroid(&bounds[a], axis)
+                    .partial_cmp(&centroid(&bounds[b], axis))
+                    .unwrap_or(::std::cmp::Ordering::Equal)
+            });
+        }
+        Some(split)
+    }
+
+    /// Walks the tree front to back calling `f` on every item whose boxes
+    /// the ray enters, and keeps the result with the smallest distance.
+    /// Subtrees further away than the best hit so far are skipped.
+    pub fn closest<R, F>(&self, ray: &Ray3, mut f: F) -> Option<(R, f64)>
+        where F: FnMut(usize) -> Option<(R, f64)>
+    {
+        let mut best: Option<(R, f64)> = None;
+        for &i in &self.unbounded {
+            if let Some((r, d)) = f(i) {
+                if best.as_ref().is_none_or(|b| d < b.1) {
+                    best = Some((r, d));
+                }
+            }
+        }
+
+        let root_dist = match self.nodes.first().and_then(|n| ray.box_dist(&n.bounds)) {
+            Some(d) => d,
+            None => return best,
+        };
+
+        let mut stack = [(0usize, 0f64); STACK_SIZE];
+        stack[0] = (0, root_dist);
+        let mut sp = 1;
+
+        while sp > 0 {
+            sp -= 1;
+            let (id, entry) = stack[sp];
+            let limit = best.as_ref().map_or(f64::INFINITY, |b| b.1);
+            if entry > limit {
+                continue;
+            }
+
+            match self.nodes[id].kind {
+                NodeKind::Leaf { first, count } => {
+                    for &i in &self.indices[first..first+count] {
+                        if let Some((r, d)) = f(i) {
+                            if best.as_ref().is_none_or(|b| d < b.1) {
+                                best = Some((r, d));
+                            }
+                        }
+                    }
+                },
+                NodeKind::Inner { left, right } => {
+                    let dl = ray.box_dist(&self.nodes[left].bounds);
+                    let dr = ray.box_dist(&self.nodes[right].bounds);
+
+                    // push the far child first so the near one pops first
+                    match (dl, dr) {
+                        (Some(dl), Some(dr)) => {
+                            let (near, far) =
+                                if dl <= dr { ((left, dl), (right, dr)) }
+                                else        { ((right, dr), (left, dl)) };
+                            stack[sp] = far;
+                            stack[sp + 1] = near;
+                            sp += 2;
+                        },
+                        (Some(dl), None) => { stack[sp] = (left, dl); sp += 1; },
+                        (None, Some(dr)) => { stack[sp] = (right, dr); sp += 1; },
+                        (None, None) => {},
+                    }
+                },
+            }
+        }
+
+        best
+    }
+
+    /// Whether `f` accepts any item whose boxes the ray enters closer than
+    /// `max_dist`. Stops at the first accepted item, so unlike `closest`
+    /// there is no ordering work.
+    pub fn any<F>(&self, ray: &Ray3, max_dist: f64, mut f: F) -> bool
+        where F: FnMut(usize) -> bool
+    {
+        if self.unbounded.iter().any(|&i| f(i)) {
+            return true;
+        }
+
+        let mut stack = [0usize; STACK_SIZE];
+        let mut sp = 0;
+
+        match self.nodes.first().and_then(|n| ray.box_dist(&n.bounds)) {
+            Some(d) if d <= max_dist => { sp = 1; },
+            _ => {},
+        }
+
+        while sp > 0 {
+            sp -= 1;
+            match self.nodes[stack[sp]].kind {
+                NodeKind::Leaf { first, count } => {
+                    if self.indices[first..first+count].iter().any(|&i| f(i)) {
+                        return true;
+                    }
+                },
+                NodeKind::Inner { left, right } => {
+                    for &child in &[left, right] {
+                        match ray.box_dist(&self.nodes[child].bounds) {
+                            Some(d) if d <= max_dist => {
+                                stack[sp] = child;
+                                sp += 1;
+                            },
+                            _ => {},
+                        }
+                    }
+                },
+            }
+        }
+
+        false
+    }
+}
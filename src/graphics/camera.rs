@@ -1,10 +1,7 @@
 use std::default::Default;
-
-use ndarray::linalg::*;
+use std::f64;
 
 use ndarray::{
-    Array,
-    Array1,
     Array2,
     ArrayBase,
 };
@@ -19,12 +16,55 @@ pub struct Camera {
 
     pub eye: Relative<Point3>,
     screen:  Relative<Screen>,
+    /// What the camera was pointed at, orbited around instead of the
+    /// default pivot.
+    target: Option<Relative<Point3>>,
+    /// Distance from the eye past which nothing is drawn.
+    far: f64,
+    projection: Projection,
+}
+
+/// How rays leave the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Projection {
+    /// From the eye through each cell, so further things look smaller.
+    #[default]
+    Perspective,
+    /// Straight out of each cell, parallel to each other, so sizes don't
+    /// change with distance. The eye only sets the direction and the view
+    /// extent is the size of the screen, see `Camera::zoom`.
+    Orthographic,
+}
+
+/// How to set up a `Camera` with `Camera::perspective`.
+///
+/// The screen becomes the near plane, sized to give the field of view, so
+/// it keeps the field of view through `Camera::resize` too.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Perspective {
+    pub eye: Point3,
+    pub target: Point3,
+    /// Roughly which way is up on the screen, needn't be perpendicular to
+    /// the view. Rows of the screen go down along `y` by default, so this
+    /// is `-y` for the usual orientation.
+    pub up: Vec3,
+    /// Vertical field of view in radians.
+    pub fov_y: f64,
+    /// Width over height of the view, see `Perspective::aspect_of`.
+    pub aspect: f64,
+    /// Distance from the eye to the screen, nothing closer is drawn.
+    pub near: f64,
+    /// Distance from the eye past which nothing is drawn.
+    pub far: f64,
 }
 
 #[derive(Debug)]
 pub struct Screen {
     cell_size: (f64, f64),
     screen_size: (usize, usize),
+    /// Height of the whole screen, kept by `resize` even while there are
+    /// no rows to tell it by.
+    height: f64,
 
     corners: (Point3, Point3, Point3, Point3),
     centers: Array2<Point3>,
@@ -42,29 +82,188 @@ impl Camera {
             eye:    Relative::new(&coord_sys, eye_pos),
             screen: Relative::new(&coord_sys,
                                   Screen::new(cell_size, screen_size)),
+            target: None,
+            far:    f64::INFINITY,
+            projection: Projection::Perspective,
             coord_sys,
         }
     }
 
+    /// A camera at `p.eye` looking at `p.target` with `screen_size` cells,
+    /// which are as wide as needed to fill `p.aspect`.
+    ///
+    /// Fails when the eye is on the target or the field of view, aspect or
+    /// distances can't give a view. Looking straight along `p.up` picks
+    /// some other up instead.
+    pub fn perspective(p: &Perspective, screen_size: (usize, usize))
+        -> Result<Self, String> {
+
+        let dist = Vec3::from(p.target - p.eye).norm();
+        if dist == 0. {
+            return Err("the eye is on the target".to_owned());
+        }
+        if !(p.fov_y > 0. && p.fov_y < f64::consts::PI) {
+            return Err(format!("field of view {} isn't between 0 and pi", p.fov_y));
+        }
+        if p.aspect.is_nan() || p.aspect <= 0. {
+            return Err(format!("aspect {} isn't above 0", p.aspect));
+        }
+        if !(p.near > 0. && p.far > p.near) {
+            return Err(format!(
+                "near {} and far {} need 0 < near < far", p.near, p.far,
+            ));
+        }
+
+        let height = 2. * p.near * (p.fov_y / 2.).tan();
+        let width = height * p.aspect;
+        let screen = Screen::with_origin(
+            (width / screen_size.0.max(1) as f64, height / screen_size.1.max(1) as f64),
+            screen_size,
+            (-width / 2., -height / 2.),
+        );
+
+        // in its own space the camera looks down -z with rows of the
+        // screen along +y, the eye `near` in front of the middle
+        let back = Vec3::from(p.eye - p.target).normalize();
+        let mut up = p.up - back * p.up.dot(&back);
+        if up.norm() <= 1e-9 * p.up.norm() {
+            // no up to speak of, use the axis least along the view
+            let (x, y, z) = back.unpack();
+            let axis = if x.abs() <= y.abs() && x.abs() <= z.abs() {
+                Vec3::new(1., 0., 0.)
+            } else if y.abs() <= z.abs() {
+                Vec3::new(0., -1., 0.)
+            } else {
+                Vec3::new(0., 0., 1.)
+            };
+            up = axis - back * axis.dot(&back);
+        }
+        let down = (up * -1.).normalize();
+        let right = down.cross_product(back);
+        let origin = Vec3::from(p.eye) - back * p.near;
+        let (r, d, b, o) = (right.unpack(), down.unpack(), back.unpack(), origin.unpack());
+
+        let mut coord_sys = SharedCoordSys::new();
+        coord_sys.apply(&Transform([
+            [r.0, d.0, b.0, o.0],
+            [r.1, d.1, b.1, o.1],
+            [r.2, d.2, b.2, o.2],
+            [0.,  0.,  0.,  1. ],
+        ]));
+        // the target is straight ahead of the eye
+        Ok(Self {
+            eye:    Relative::new(&coord_sys, Point3::new(0., 0., p.near)),
+            screen: Relative::new(&coord_sys, screen),
+            target: Some(Relative::new(&coord_sys, Point3::new(0., 0., p.near - dist))),
+            far:    p.far,
+            projection: Projection::Perspective,
+            coord_sys,
+        })
+    }
+
     pub fn apply_rel(&mut self, t: Transform) -> &mut Self {
         self.coord_sys.apply_rel(t);
         self
     }
 
+    /// What orbits turn around: the target of a `perspective` camera,
+    /// otherwise halfway between the eye and the middle of the screen.
     pub fn get_pivot(&self) -> Point3 {
+        self.coord_sys.apply_to(self.rel_pivot())
+    }
+
+    /// `get_pivot` in the camera's own coordinates.
+    fn rel_pivot(&self) -> Point3 {
+        if let Some(ref target) = self.target {
+            return *target.as_rel();
+        }
         let (x, y) = self.get_screen_size();
-        (self.eye.get_abs() + self.get_center((x/2, y/2)).unwrap()) / 2.
+        let center = *self.screen.as_rel().get_center((x/2, y/2)).unwrap();
+        (*self.eye.as_rel() + center) / 2.
+    }
+
+    /// Moves the camera `d` along its view, towards the pivot when
+    /// positive. The pivot stays where it is, so orbits keep turning around
+    /// the same point however close the camera gets.
+    pub fn dolly(&mut self, d: f64) -> &mut Self {
+        let mut pivot = self.rel_pivot();
+        pivot.apply(&Transform::translate(Vec3::new(0., 0., d)));
+        self.target = Some(Relative::new(&self.coord_sys, pivot));
+        self.apply_rel(Transform::translate(Vec3::new(0., 0., -d)))
+    }
+
+    pub fn get_far(&self) -> f64 {
+        self.far
+    }
+
+    pub fn set_far(&mut self, far: f64) -> &mut Self {
+        self.far = far;
+        self
+    }
+
+    pub fn get_projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) -> &mut Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Shrinks the screen around its middle by `factor`, so what's in view
+    /// looks `factor` times bigger. Narrows the field of view in
+    /// perspective, the view extent in orthographic projection.
+    ///
+    /// Panics unless `factor` is above 0 and finite.
+    pub fn zoom(&mut self, factor: f64) -> &mut Self {
+        self.screen.as_rel_mut().zoom(factor);
+        self
+    }
+
+    /// How far along `ray` from the screen hits are drawn, for rays from
+    /// `get_ray`.
+    pub fn far_along(&self, ray: &Ray3) -> f64 {
+        let to_screen = Vec3::from(ray.origin() - self.eye.get_abs());
+        match self.projection {
+            Projection::Perspective => self.far - to_screen.norm(),
+            // rays start level with each other, as far from the eye
+            // as the screen is
+            Projection::Orthographic => self.far - to_screen.dot(&self.get_view_dir()),
+        }
+    }
+
+    /// Unit normal of the screen, facing away from the eye.
+    pub fn get_view_dir(&self) -> Vec3 {
+        let (c0, c1, c3) = self.screen.abs_map_ref(|s, cs| (
+            cs.apply_to(s.corners.0),
+            cs.apply_to(s.corners.1),
+            cs.apply_to(s.corners.3),
+        ));
+        let n = Vec3::from((c1 - c0).cross_product(c3 - c0)).normalize();
+        if n.dot(&Vec3::from(c0 - self.eye.get_abs())) < 0. { n * -1. } else { n }
     }
 
     pub fn get_screen_size(&self) -> &(usize, usize) {
         &self.screen.as_rel().screen_size
     }
 
+    pub fn get_cell_size(&self) -> &(f64, f64) {
+        &self.screen.as_rel().cell_size
+    }
+
+    /// Changes the number and shape of the cells on the screen, see
+    /// `Screen::resize`.
+    pub fn resize(&mut self, screen_size: (usize, usize), cell_aspect: f64)
+        -> &mut Self {
+        self.screen.as_rel_mut().resize(screen_size, cell_aspect);
+        self
+    }
+
     pub fn get_screen_centers(&self) -> Array2<Point3> {
         self.screen
              .abs_map_ref(
                  |s, cs| s.centers
-                          .map(|c| cs.apply_to(c.clone()))
+                          .map(|c| cs.apply_to(*c))
              )
     }
 
@@ -72,17 +271,18 @@ impl Camera {
         self.screen
             .abs_map_ref(
                 |s, cs| s.get_center(coords)
-                         .map(|c| cs.apply_to(c.clone()))
+                         .map(|c| cs.apply_to(*c))
             )
     }
 
     pub fn get_ray(&self, coords: (usize, usize)) -> Option<Ray3> {
-        self.get_center(coords).map(|origin|
-            Ray3::new(
-                origin.clone(),
-                Vec3::from(origin - self.eye.get_abs()),
-            )
-        )
+        let origin = self.get_center(coords)?;
+        Some(match self.projection {
+            Projection::Perspective =>
+                Ray3::new(origin, Vec3::from(origin - self.eye.get_abs())),
+            Projection::Orthographic =>
+                Ray3::new(origin, self.get_view_dir()),
+        })
     }
 }
 
@@ -100,13 +300,51 @@ impl Default for Camera {
                 Point3::new(total_dims.0/2., total_dims.1/2., 45.),
             ),
             screen: Relative::new(&coord_sys, screen),
+            target: None,
+            far:    f64::INFINITY,
+            projection: Projection::Perspective,
             coord_sys,
         }
     }
 }
 
+impl Perspective {
+    /// Width over height of `screen_size` cells that are `cell_aspect`
+    /// times as wide as they are tall, e.g. `Style::cell_aspect`, so the
+    /// view isn't squashed by the shape of terminal cells.
+    pub fn aspect_of(screen_size: (usize, usize), cell_aspect: f64) -> f64 {
+        screen_size.0 as f64 * cell_aspect / screen_size.1.max(1) as f64
+    }
+}
+
+impl Default for Perspective {
+    /// A 60 degree view from the origin down `-z`, square, drawing
+    /// everything from 1 unit away.
+    fn default() -> Self {
+        Perspective {
+            eye: Point3::new(0., 0., 0.),
+            target: Point3::new(0., 0., -1.),
+            up: Vec3::new(0., -1., 0.),
+            fov_y: f64::consts::PI / 3.,
+            aspect: 1.,
+            near: 1.,
+            far: f64::INFINITY,
+        }
+    }
+}
+
 impl Screen {
     pub fn new(cell_size: (f64, f64), screen_size: (usize, usize)) -> Self {
+        Self::with_origin(cell_size, screen_size, (0., 0.))
+    }
+
+    fn with_origin(
+        cell_size: (f64, f64),
+        screen_size: (usize, usize),
+        (ox, oy): (f64, f64),
+
+        ) -> Self {
+
         let total_dims = (
             screen_size.0 as f64 * cell_size.0,
             screen_size.1 as f64 * cell_size.1,
@@ -114,26 +352,85 @@ impl Screen {
         Screen {
             cell_size,
             screen_size,
+            height: total_dims.1,
 
             corners: (
-                Point3::new(0.,           0., 0.),
-                Point3::new(total_dims.0, 0., 0.),
-                Point3::new(total_dims.0, total_dims.1, 0.),
-                Point3::new(0.,           total_dims.1, 0.),
+                Point3::new(ox,                oy, 0.),
+                Point3::new(ox + total_dims.0, oy, 0.),
+                Point3::new(ox + total_dims.0, oy + total_dims.1, 0.),
+                Point3::new(ox,                oy + total_dims.1, 0.),
             ),
 
             centers: {
                 let cz = cell_size;
                 let sz = screen_size;
                 ArrayBase::from_shape_fn((sz.1, sz.0), |(y, x)| {
-                    Point3::new(x as f64 * cz.0 + cell_size.0/2.,
-                                y as f64 * cz.1 + cell_size.1/2.,
+                    Point3::new(ox + x as f64 * cz.0 + cell_size.0/2.,
+                                oy + y as f64 * cz.1 + cell_size.1/2.,
                                 0.)
                 })
             },
         }
     }
 
+    /// Rebuilds the screen with `screen_size` cells that are `cell_aspect`
+    /// times as wide as they are tall, keeping its height and its middle
+    /// where they were so the view stays framed the same way vertically and
+    /// only gets wider or narrower.
+    pub fn resize(&mut self, screen_size: (usize, usize), cell_aspect: f64)
+        -> &mut Self {
+
+        let aspect = self.cell_size.0 / self.cell_size.1;
+        if screen_size == self.screen_size && cell_aspect == aspect {
+            return self;
+        }
+        let (x0, y0, _) = self.corners.0.unpack();
+        let (x1, y1, _) = self.corners.2.unpack();
+        let mid = ((x0 + x1) / 2., (y0 + y1) / 2.);
+
+        let height = self.height;
+        let cell_height = if screen_size.1 > 0 {
+            height / screen_size.1 as f64
+        } else {
+            self.cell_size.1
+        };
+        let cell_size = (cell_height * cell_aspect, cell_height);
+        let total_dims = (
+            screen_size.0 as f64 * cell_size.0,
+            screen_size.1 as f64 * cell_size.1,
+        );
+
+        *self = Self::with_origin(
+            cell_size,
+            screen_size,
+            (mid.0 - total_dims.0 / 2., mid.1 - total_dims.1 / 2.),
+        );
+        self.height = height;
+        self
+    }
+
+    /// Scales the cells by `1/factor` keeping the middle of the screen
+    /// where it was. Panics unless `factor` is above 0 and finite.
+    pub fn zoom(&mut self, factor: f64) -> &mut Self {
+        assert!(factor > 0. && factor.is_finite(), "zoom factor {} isn't above 0", factor);
+        let (x0, y0, _) = self.corners.0.unpack();
+        let (x1, y1, _) = self.corners.2.unpack();
+        let mid = ((x0 + x1) / 2., (y0 + y1) / 2.);
+        let cell_size = (self.cell_size.0 / factor, self.cell_size.1 / factor);
+        let total_dims = (
+            self.screen_size.0 as f64 * cell_size.0,
+            self.screen_size.1 as f64 * cell_size.1,
+        );
+        let height = self.height / factor;
+        *self = Self::with_origin(
+            cell_size,
+            self.screen_size,
+            (mid.0 - total_dims.0 / 2., mid.1 - total_dims.1 / 2.),
+        );
+        self.height = height;
+        self
+    }
+
     pub fn get_center(&self, (x, y): (usize, usize)) -> Option<&Point3> {
         self.centers.get((y, x))
     }
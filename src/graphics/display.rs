@@ -0,0 +1,672 @@
+//! Terminal output that only rewrites what changed since the last frame.
+
+use std::env;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use termion::color::{AnsiValue, Bg, Fg, Reset, Rgb};
+use termion::cursor;
+
+use super::scene::{Cell, RenderedScene};
+
+/// One terminal character cell, `None` colours are the terminal defaults.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    pub ch: char,
+    pub fg: Option<Rgb>,
+    pub bg: Option<Rgb>,
+}
+
+impl Glyph {
+    pub const BLANK: Glyph = Glyph { ch: ' ', fg: None, bg: None };
+
+    /// Packs two vertically stacked cells into a half block character.
+    pub fn half_block(upper: &Cell, lower: &Cell) -> Glyph {
+        let color = |c: &Cell| match c {
+            Cell::Empty => None,
+            Cell::Hit{color, ..} => Some(*color),
+        };
+        match (color(upper), color(lower)) {
+            (bg, Some(fg)) => Glyph { ch: '▄', fg: Some(fg), bg },
+            (Some(fg), None) => Glyph { ch: '▀', fg: Some(fg), bg: None },
+            (None, None) => Glyph::BLANK,
+        }
+    }
+
+    /// Whether the foreground colour shows at all.
+    fn uses_fg(&self) -> bool {
+        self.ch != ' '
+    }
+}
+
+/// Characters from darkest to brightest used to draw shading as text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ramp(Vec<char>);
+
+impl Ramp {
+    pub const DEFAULT: &'static str = " .:-=+*#%@";
+
+    /// The first character is used for empty cells, so there must be at
+    /// least two.
+    pub fn new(chars: &str) -> Result<Ramp, String> {
+        let chars: Vec<char> = chars.chars().collect();
+        if chars.len() < 2 {
+            return Err(format!(
+                "a ramp needs at least 2 characters, found {}", chars.len()));
+        }
+        Ok(Ramp(chars))
+    }
+
+    pub fn chars(&self) -> &[char] {
+        &self.0
+    }
+
+    /// Picks the character for a cell by the light reaching it. Hits never
+    /// get the empty cell character, so unlit surfaces still show.
+    pub fn pick(&self, cell: &Cell) -> char {
+        match cell {
+            Cell::Empty => self.0[0],
+            Cell::Hit{light, ..} => {
+                let steps = (self.0.len() - 2) as f64;
+                self.0[1 + (light.clamp(0., 1.) * steps).round() as usize]
+            },
+        }
+    }
+}
+
+impl Default for Ramp {
+    fn default() -> Self {
+        Ramp::new(Ramp::DEFAULT).unwrap()
+    }
+}
+
+/// How cells are turned into characters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Style {
+    /// `▀`/`▄` half blocks, two cells stacked in each character.
+    HalfBlock,
+    /// One character from the ramp per cell, optionally coloured with the
+    /// cell's colour.
+    Ramp { ramp: Ramp, color: bool },
+    /// Braille patterns, a 2x4 grid of cells per character with a dot for
+    /// every hit, coloured with the average colour of the hits.
+    Braille,
+}
+
+/// Bits of the braille dots, indexed by `[y][x]` within the character.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [
+    [0x01, 0x08],
+    [0x02, 0x10],
+    [0x04, 0x20],
+    [0x40, 0x80],
+];
+
+impl Style {
+    /// Cells across and down that go into one character.
+    pub fn cells_per_glyph(&self) -> (usize, usize) {
+        match self {
+            Style::HalfBlock => (1, 2),
+            Style::Ramp{..} => (1, 1),
+            Style::Braille => (2, 4),
+        }
+    }
+
+    /// Width over height of the cells, for character cells about twice
+    /// as tall as they are wide.
+    pub fn cell_aspect(&self) -> f64 {
+        let (x, y) = self.cells_per_glyph();
+        y as f64 / (2. * x as f64)
+    }
+
+    /// Size in characters of the output for `rows` of cells.
+    pub fn glyphs_size(&self, rows: &[Vec<Cell>]) -> (usize, usize) {
+        let (w, h) = self.cells_per_glyph();
+        (
+            rows.first().map_or(0, |r| r.len()).div_ceil(w),
+            rows.len().div_ceil(h),
+        )
+    }
+
+    /// The character at `(x, y)`, cells past the edges count as empty.
+    pub fn glyph(&self, rows: &[Vec<Cell>], (x, y): (usize, usize)) -> Glyph {
+        let (w, h) = self.cells_per_glyph();
+        let cell = |dx: usize, dy: usize| rows.get(y * h + dy)
+            .and_then(|r| r.get(x * w + dx))
+            .unwrap_or(&Cell::Empty);
+
+        match self {
+            Style::HalfBlock => Glyph::half_block(cell(0, 0), cell(0, 1)),
+            Style::Ramp{ramp, color} => {
+                let c = cell(0, 0);
+                Glyph {
+                    ch: ramp.pick(c),
+                    fg: match c {
+                        Cell::Hit{color: rgb, ..} if *color => Some(*rgb),
+                        _ => None,
+                    },
+                    bg: None,
+                }
+            },
+            Style::Braille => {
+                let mut dots = 0;
+                let mut sum = (0, 0, 0);
+                let mut hits = 0;
+                for (dy, row) in BRAILLE_DOTS.iter().enumerate() {
+                    for (dx, bit) in row.iter().enumerate() {
+                        if let Cell::Hit{color: Rgb(r, g, b), ..} = cell(dx, dy) {
+                            dots |= bit;
+                            sum = (sum.0 + *r as u32, sum.1 + *g as u32, sum.2 + *b as u32);
+                            hits += 1;
+                        }
+                    }
+                }
+                if hits == 0 {
+                    return Glyph::BLANK;
+                }
+                Glyph {
+                    ch: ::std::char::from_u32(0x2800 + dots).unwrap(),
+                    fg: Some(Rgb(
+                        (sum.0 / hits) as u8,
+                        (sum.1 / hits) as u8,
+                        (sum.2 / hits) as u8,
+                    )),
+                    bg: None,
+                }
+            },
+        }
+    }
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "blocks" => Ok(Style::HalfBlock),
+            "ascii" => Ok(Style::Ramp { ramp: Ramp::default(), color: false }),
+            "ascii-color" => Ok(Style::Ramp { ramp: Ramp::default(), color: true }),
+            "braille" => Ok(Style::Braille),
+            _ => Err(format!(
+                "unknown style `{}`, expected blocks, ascii, ascii-color or braille", s)),
+        }
+    }
+}
+
+/// How many colours the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// 24-bit colour escapes.
+    TrueColor,
+    /// The xterm 256 colour palette: a 6x6x6 cube and a grey ramp.
+    Ansi256,
+    /// The 8 basic colours and their bright variants.
+    Ansi16,
+    /// No colour escapes at all, only the shape of the glyphs.
+    Mono,
+}
+
+/// xterm's defaults for the 16 ANSI colours.
+const ANSI16: [(u8, u8, u8); 16] = [
+    (0, 0, 0),       (205, 0, 0),     (0, 205, 0),     (205, 205, 0),
+    (0, 0, 238),     (205, 0, 205),   (0, 205, 205),   (229, 229, 229),
+    (127, 127, 127), (255, 0, 0),     (0, 255, 0),     (255, 255, 0),
+    (92, 92, 255),   (255, 0, 255),   (0, 255, 255),   (255, 255, 255),
+];
+
+/// Channel levels of the 256 colour cube.
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn dist2(Rgb(r0, g0, b0): Rgb, (r1, g1, b1): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r0, r1) + d(g0, g1) + d(b0, b1)
+}
+
+impl ColorMode {
+    /// Guesses the mode from the `COLORTERM` and `TERM` environment
+    /// variables.
+    pub fn detect() -> Self {
+        Self::from_env(
+            env::var("COLORTERM").ok().as_deref(),
+            env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    pub fn from_env(colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if let Some("truecolor") | Some("24bit") = colorterm {
+            return ColorMode::TrueColor;
+        }
+        match term {
+            None | Some("") | Some("dumb") => ColorMode::Mono,
+            Some(t) if t.contains("truecolor") || t.contains("24bit")
+                || t.ends_with("-direct") => ColorMode::TrueColor,
+            Some(t) if t.contains("256col") => ColorMode::Ansi256,
+            Some(_) => ColorMode::Ansi16,
+        }
+    }
+
+    /// Index into the 256 colour palette closest to `c`.
+    fn ansi256(c: Rgb) -> u8 {
+        let level = |x: u8| {
+            (0..CUBE.len())
+                .min_by_key(|&i| (CUBE[i] as i32 - x as i32).abs())
+                .unwrap()
+        };
+        let (r, g, b) = (level(c.0), level(c.1), level(c.2));
+        let cube = (CUBE[r], CUBE[g], CUBE[b]);
+
+        let avg = (c.0 as u32 + c.1 as u32 + c.2 as u32) / 3;
+        let grey = ((avg.max(8) - 8 + 5) / 10).min(23) as u8;
+        let grey_v = 8 + grey * 10;
+
+        if dist2(c, (grey_v, grey_v, grey_v)) < dist2(c, cube) {
+            232 + grey
+        } else {
+            (16 + 36 * r + 6 * g + b) as u8
+        }
+    }
+
+    fn palette256(i: u8) -> (u8, u8, u8) {
+        match i {
+            0..=15 => ANSI16[i as usize],
+            16..=231 => {
+                let i = (i - 16) as usize;
+                (CUBE[i / 36], CUBE[i / 6 % 6], CUBE[i % 6])
+            },
+            _ => {
+                let v = 8 + (i - 232) * 10;
+                (v, v, v)
+            },
+        }
+    }
+
+    fn ansi16(c: Rgb) -> u8 {
+        (0..16).min_by_key(|&i| dist2(c, ANSI16[i as usize])).unwrap()
+    }
+
+    /// Snaps the colours of `g` to ones this mode can show, so glyphs that
+    /// look the same on the terminal also compare equal.
+    pub fn quantize(&self, g: Glyph) -> Glyph {
+        let rgb = |(r, g, b)| Rgb(r, g, b);
+        let snap = |c: Option<Rgb>| c.map(|c| match *self {
+            ColorMode::TrueColor | ColorMode::Mono => c,
+            ColorMode::Ansi256 => rgb(Self::palette256(Self::ansi256(c))),
+            ColorMode::Ansi16 => rgb(ANSI16[Self::ansi16(c) as usize]),
+        });
+        match *self {
+            // a filled background is all that's left of the lower half
+            ColorMode::Mono => Glyph {
+                ch: match (g.ch, g.bg) {
+                    ('▄', Some(_)) | ('▀', Some(_)) => '█',
+                    (ch, _) => ch,
+                },
+                fg: None,
+                bg: None,
+            },
+            _ => Glyph { ch: g.ch, fg: snap(g.fg), bg: snap(g.bg) },
+        }
+    }
+
+    fn write_color<W: Write>(&self, o: &mut W, c: Option<Rgb>, fg: bool)
+        -> io::Result<()> {
+
+        match (*self, c) {
+            (ColorMode::Mono, _) => Ok(()),
+            (_, None) if fg => write!(o, "{}", Fg(Reset)),
+            (_, None) => write!(o, "{}", Bg(Reset)),
+            (ColorMode::TrueColor, Some(c)) if fg => write!(o, "{}", Fg(c)),
+            (ColorMode::TrueColor, Some(c)) => write!(o, "{}", Bg(c)),
+            (ColorMode::Ansi256, Some(c)) if fg => write!(o, "{}", Fg(AnsiValue(Self::ansi256(c)))),
+            (ColorMode::Ansi256, Some(c)) => write!(o, "{}", Bg(AnsiValue(Self::ansi256(c)))),
+            (ColorMode::Ansi16, Some(c)) => {
+                // the plain SGR codes, termion's named colours use the
+                // 256 colour escapes
+                let i = Self::ansi16(c);
+                let base = match (fg, i < 8) {
+                    (true, true) => 30,
+                    (true, false) => 90 - 8,
+                    (false, true) => 40,
+                    (false, false) => 100 - 8,
+                };
+                write!(o, "\x1b[{}m", base + i)
+            },
+        }
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "truecolor" | "24bit" => Ok(ColorMode::TrueColor),
+            "256" => Ok(ColorMode::Ansi256),
+            "16" => Ok(ColorMode::Ansi16),
+            "mono" => Ok(ColorMode::Mono),
+            _ => Err(format!(
+                "unknown colour mode `{}`, expected truecolor, 256, 16 or mono", s)),
+        }
+    }
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ColorMode::TrueColor => "truecolor",
+            ColorMode::Ansi256 => "256",
+            ColorMode::Ansi16 => "16",
+            ColorMode::Mono => "mono",
+        })
+    }
+}
+
+/// Writes glyphs, only changing colours when they differ from the ones
+/// already set.
+pub(crate) struct Pen {
+    mode: ColorMode,
+    // `None` while unknown
+    fg: Option<Option<Rgb>>,
+    bg: Option<Option<Rgb>>,
+}
+
+impl Pen {
+    pub(crate) fn new(mode: ColorMode) -> Self {
+        Pen { mode, fg: None, bg: None }
+    }
+
+    /// Writes `g`, which should already be quantised for the mode.
+    pub(crate) fn glyph<W: Write>(&mut self, o: &mut W, g: Glyph) -> io::Result<()> {
+        if g.uses_fg() && self.fg != Some(g.fg) {
+            self.mode.write_color(o, g.fg, true)?;
+            self.fg = Some(g.fg);
+        }
+        if self.bg != Some(g.bg) {
+            self.mode.write_color(o, g.bg, false)?;
+            self.bg = Some(g.bg);
+        }
+        write!(o, "{}", g.ch)
+    }
+
+    /// Puts the terminal colours back to their defaults.
+    pub(crate) fn reset<W: Write>(&mut self, o: &mut W) -> io::Result<()> {
+        self.mode.write_color(o, None, true)?;
+        self.mode.write_color(o, None, false)?;
+        self.fg = Some(None);
+        self.bg = Some(None);
+        Ok(())
+    }
+}
+
+/// Front and back buffers of `Glyph`s drawn at a fixed place on the
+/// terminal.
+///
+/// `draw` composes the back buffer from a `RenderedScene`, writes only the
+/// glyphs that differ from the front buffer, which mirrors what is on the
+/// terminal, and then swaps the two.
+pub struct Display {
+    /// 1-based terminal column and row of the top left glyph.
+    origin: (u16, u16),
+    size: (usize, usize),
+    style: Style,
+    mode: ColorMode,
+    front: Vec<Glyph>,
+    back: Vec<Glyph>,
+    /// `false` when the terminal may no longer match `front`.
+    valid: bool,
+    out: Vec<u8>,
+}
+
+impl Display {
+    /// Draws half blocks in the `ColorMode::detect` colour mode.
+    pub fn new(origin: (u16, u16)) -> Self {
+        Display {
+            origin,
+            size: (0, 0),
+            style: Style::HalfBlock,
+            mode: ColorMode::detect(),
+            front: Vec::new(),
+            back: Vec::new(),
+            valid: false,
+            out: Vec::new(),
+        }
+    }
+
+    /// Forces the next `draw` to rewrite every glyph, for when something
+    /// else has drawn over or cleared the terminal.
+    pub fn invalidate(&mut self) {
+        self.valid = false;
+    }
+
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
+    pub fn set_style(&mut self, style: Style) -> &mut Self {
+        if style != self.style {
+            self.style = style;
+            self.valid = false;
+        }
+        self
+    }
+
+    pub fn color_mode(&self) -> ColorMode {
+        self.mode
+    }
+
+    pub fn set_color_mode(&mut self, mode: ColorMode) -> &mut Self {
+        if mode != self.mode {
+            self.mode = mode;
+            self.valid = false;
+        }
+        self
+    }
+
+    /// The glyph at the 1-based terminal column and row `pos`, as reported
+    /// by mouse events, or `None` outside of what was last drawn.
+    pub fn glyph_at(&self, pos: (u16, u16)) -> Option<(usize, usize)> {
+        let x = pos.0.checked_sub(self.origin.0)? as usize;
+        let y = pos.1.checked_sub(self.origin.1)? as usize;
+        if x < self.size.0 && y < self.size.1 { Some((x, y)) } else { None }
+    }
+
+    /// Bytes written by the last `draw`.
+    pub fn bytes_written(&self) -> usize {
+        self.out.len()
+    }
+
+    /// Draws `scene` and returns the number of bytes written.
+    pub fn draw<W: Write>(&mut self, scene: &RenderedScene, o: &mut W)
+        -> io::Result<usize> {
+
+        let rows = scene.rows();
+        let size = self.style.glyphs_size(rows);
+        if size != self.size {
+            self.size = size;
+            self.front = vec![Glyph::BLANK; size.0 * size.1];
+            self.back = vec![Glyph::BLANK; size.0 * size.1];
+            self.valid = false;
+        }
+
+        for (i, g) in self.back.iter_mut().enumerate() {
+            let pos = (i % size.0, i / size.0);
+            *g = self.mode.quantize(self.style.glyph(rows, pos));
+        }
+
+        self.write_changes()?;
+        o.write_all(&self.out)?;
+        o.flush()?;
+
+        ::std::mem::swap(&mut self.front, &mut self.back);
+        self.valid = true;
+        Ok(self.out.len())
+    }
+
+    fn write_changes(&mut self) -> io::Result<()> {
+        let out = &mut self.out;
+        out.clear();
+
+        // where the terminal cursor is, `None` while unknown
+        let mut at: Option<(usize, usize)> = None;
+        let mut pen = Pen::new(self.mode);
+
+        let width = self.size.0.max(1);
+        for (i, (g, old)) in self.back.iter().zip(self.front.iter()).enumerate() {
+            if self.valid && g == old {
+                continue;
+            }
+            let pos = (i % width, i / width);
+
+            if out.is_empty() {
+                write!(out, "{}", cursor::Hide)?;
+            }
+            if at != Some(pos) {
+                write!(out, "{}", cursor::Goto(
+                    self.origin.0 + pos.0 as u16,
+                    self.origin.1 + pos.1 as u16,
+                ))?;
+            }
+            pen.glyph(out, *g)?;
+            at = Some((pos.0 + 1, pos.1));
+        }
+
+        if !out.is_empty() {
+            // don't leak colours into whatever is written next
+            pen.reset(out)?;
+            write!(out, "{}", cursor::Show)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::*;
+    use graphics::scene::{Object, Scene};
+
+    /// A 4x4 render of a cube filling the middle of the default view.
+    fn cube_render() -> RenderedScene {
+        let mut scene = Scene::new();
+        scene.camera.resize((4, 4), 1.);
+        scene.add(Object::cube(
+            Rgb(255, 85, 85), Point3::new(2., 2., -10.), Point3::new(14., 14., -4.)));
+        let mut rendered = scene.empty_render();
+        scene.render(&mut rendered);
+        rendered
+    }
+
+    #[test]
+    fn unchanged_frame_writes_nothing() {
+        let rendered = cube_render();
+        let mut display = Display::new((1, 1));
+        display.set_color_mode(ColorMode::TrueColor);
+
+        let mut out = Vec::new();
+        assert!(display.draw(&rendered, &mut out).unwrap() > 0);
+        out.clear();
+        assert_eq!(display.draw(&rendered, &mut out).unwrap(), 0);
+        assert!(out.is_empty());
+
+        // until something else may have drawn over it
+        display.invalidate();
+        assert!(display.draw(&rendered, &mut out).unwrap() > 0);
+    }
+
+    #[test]
+    fn color_mode_from_env() {
+        for &(colorterm, term, mode) in &[
+            (Some("truecolor"), Some("xterm"), ColorMode::TrueColor),
+            (Some("24bit"), None, ColorMode::TrueColor),
+            (None, None, ColorMode::Mono),
+            (None, Some(""), ColorMode::Mono),
+            (Some("truecolor"), Some("dumb"), ColorMode::TrueColor),
+            (None, Some("dumb"), ColorMode::Mono),
+            (None, Some("xterm-direct"), ColorMode::TrueColor),
+            (None, Some("iterm2-truecolor"), ColorMode::TrueColor),
+            (Some("yes"), Some("screen-256color"), ColorMode::Ansi256),
+            (None, Some("xterm-256color"), ColorMode::Ansi256),
+            (None, Some("xterm"), ColorMode::Ansi16),
+            (None, Some("linux"), ColorMode::Ansi16),
+        ] {
+            assert_eq!(ColorMode::from_env(colorterm, term), mode, "{:?} {:?}", colorterm, term);
+        }
+    }
+
+    #[test]
+    fn ansi_palettes() {
+        for &(c, i) in &[
+            (Rgb(0, 0, 0), 16),
+            (Rgb(255, 255, 255), 231),
+            (Rgb(255, 0, 0), 196),
+            (Rgb(0, 255, 0), 46),
+            (Rgb(0, 0, 255), 21),
+            (Rgb(95, 135, 175), 67),
+            (Rgb(128, 128, 128), 244),
+            (Rgb(100, 100, 100), 241),
+        ] {
+            assert_eq!(ColorMode::ansi256(c), i, "{:?}", c);
+        }
+        for &(c, i) in &[
+            (Rgb(0, 0, 0), 0),
+            (Rgb(205, 0, 0), 1),
+            (Rgb(255, 0, 0), 9),
+            (Rgb(0, 0, 238), 4),
+            (Rgb(120, 120, 120), 8),
+            (Rgb(255, 255, 255), 15),
+        ] {
+            assert_eq!(ColorMode::ansi16(c), i, "{:?}", c);
+        }
+
+        let mut out = Vec::new();
+        ColorMode::Ansi16.write_color(&mut out, Some(Rgb(255, 0, 0)), true).unwrap();
+        ColorMode::Ansi16.write_color(&mut out, Some(Rgb(205, 0, 0)), false).unwrap();
+        assert_eq!(out, b"\x1b[91m\x1b[41m");
+    }
+
+    fn hit(light: f64) -> Cell {
+        Cell::Hit {
+            color: Rgb(255, 255, 255),
+            dist: 1.,
+            normal: Vec3::new(0., 0., 1.),
+            light,
+        }
+    }
+
+    #[test]
+    fn ramp_ends() {
+        let ramp = Ramp::default();
+        assert_eq!(ramp.pick(&Cell::Empty), ' ');
+        assert_eq!(ramp.pick(&hit(0.)), '.');
+        assert_eq!(ramp.pick(&hit(1.)), '@');
+        // light past either end is clamped
+        assert_eq!(ramp.pick(&hit(-0.5)), '.');
+        assert_eq!(ramp.pick(&hit(3.)), '@');
+
+        let two = Ramp::new(" #").unwrap();
+        assert_eq!((two.pick(&hit(0.)), two.pick(&hit(1.))), ('#', '#'));
+        assert!(Ramp::new("#").is_err());
+    }
+
+    #[test]
+    fn braille_dots() {
+        // Unicode numbers the dots down the left column, then the right,
+        // with the bottom row added last
+        for &((x, y), ch) in &[
+            ((0, 0), '⠁'), ((0, 1), '⠂'), ((0, 2), '⠄'),
+            ((1, 0), '⠈'), ((1, 1), '⠐'), ((1, 2), '⠠'),
+            ((0, 3), '⡀'), ((1, 3), '⢀'),
+        ] {
+            let mut rows = vec![vec![Cell::Empty; 2]; 4];
+            rows[y][x] = hit(1.);
+            assert_eq!(Style::Braille.glyph(&rows, (0, 0)).ch, ch, "dot at {:?}", (x, y));
+        }
+
+        let mut rows = vec![vec![hit(1.); 2]; 4];
+        if let Cell::Hit{ref mut color, ..} = rows[0][0] {
+            *color = Rgb(0, 0, 0);
+        }
+        let full = Style::Braille.glyph(&rows, (0, 0));
+        assert_eq!((full.ch, full.fg), ('⣿', Some(Rgb(223, 223, 223))));
+        assert_eq!(Style::Braille.glyph(&rows, (1, 1)), Glyph::BLANK);
+    }
+}
@@ -0,0 +1,224 @@
+//! Headless output of a `RenderedScene` as image files, one pixel per cell.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use termion::color::Rgb;
+
+use super::scene::{Cell, RenderedScene};
+
+/// Colour of empty cells in images.
+pub const BACKGROUND: Rgb = Rgb(0, 0, 0);
+
+/// Largest block a stored (uncompressed) deflate block can hold.
+const STORED_MAX: usize = 0xffff;
+
+impl RenderedScene {
+    fn pixels(&self) -> (usize, usize, Vec<u8>) {
+        let rows = self.rows();
+        let w = rows.first().map_or(0, |r| r.len());
+        let mut data = Vec::with_capacity(w * rows.len() * 3);
+        for cell in rows.iter().flat_map(|r| r.iter()) {
+            let Rgb(r, g, b) = match cell {
+                Cell::Empty => BACKGROUND,
+                Cell::Hit{color, ..} => *color,
+            };
+            data.extend_from_slice(&[r, g, b]);
+        }
+        (w, rows.len(), data)
+    }
+
+    /// Writes a binary (P6) PPM image.
+    pub fn write_ppm<W: Write>(&self, o: &mut W) -> io::Result<()> {
+        let (w, h, data) = self.pixels();
+        write!(o, "P6\n{} {}\n255\n", w, h)?;
+        o.write_all(&data)
+    }
+
+    /// Writes an 8-bit RGB PNG image. The pixel data is stored without
+    /// compression, which keeps this free of dependencies.
+    pub fn write_png<W: Write>(&self, o: &mut W) -> io::Result<()> {
+        let (w, h, data) = self.pixels();
+
+        // every scanline starts with its filter type, 0 for none
+        let mut raw = Vec::with_capacity(h * (w * 3 + 1));
+        for line in data.chunks(w.max(1) * 3) {
+            raw.push(0);
+            raw.extend_from_slice(line);
+        }
+
+        let mut zlib = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(STORED_MAX).peekable();
+        if blocks.peek().is_none() {
+            zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+        }
+        while let Some(block) = blocks.next() {
+            let last = blocks.peek().is_none();
+            let len = block.len() as u16;
+            zlib.push(last as u8);
+            zlib.extend_from_slice(&len.to_le_bytes());
+            zlib.extend_from_slice(&(!len).to_le_bytes());
+            zlib.extend_from_slice(block);
+        }
+        zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(w as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(h as u32).to_be_bytes());
+        // 8 bits per channel, truecolour, deflate, no filtering, no interlace
+        ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+        o.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_chunk(o, b"IHDR", &ihdr)?;
+        write_chunk(o, b"IDAT", &zlib)?;
+        write_chunk(o, b"IEND", &[])
+    }
+
+    /// Saves the image as PNG or PPM, picked by the extension of `path`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let ext = path.extension().and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let png = match ext.as_deref() {
+            Some("png") => true,
+            Some("ppm") => false,
+            _ => return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("can't tell the image format of `{}`, use .png or .ppm",
+                        path.display()),
+            )),
+        };
+
+        let mut o = BufWriter::new(File::create(path)?);
+        if png {
+            self.write_png(&mut o)?;
+        } else {
+            self.write_ppm(&mut o)?;
+        }
+        o.flush()
+    }
+}
+
+fn write_chunk<W: Write>(o: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    o.write_all(&(data.len() as u32).to_be_bytes())?;
+    o.write_all(kind)?;
+    o.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data.iter()));
+    o.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a, I: Iterator<Item = &'a u8>>(bytes: I) -> u32 {
+    !bytes.fold(!0u32, |crc, &b| {
+        (0..8).fold(crc ^ b as u32, |c, _| {
+            if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 }
+        })
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &x| {
+        let a = (a + x as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graphics::*;
+    use graphics::scene::{Object, Scene};
+
+    fn be32(b: &[u8]) -> u32 {
+        u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+    }
+
+    /// A cube over an empty background, big enough that the pixel data
+    /// takes more than one stored block.
+    fn cube_render(size: (usize, usize)) -> RenderedScene {
+        let mut scene = Scene::new();
+        scene.camera.resize(size, 1.);
+        scene.add(Object::cube(
+            Rgb(55, 155, 255), Point3::new(4., 4., -10.), Point3::new(12., 12., -4.)));
+        let mut rendered = scene.empty_render();
+        scene.render(&mut rendered);
+        rendered
+    }
+
+    #[test]
+    fn checksums() {
+        // the check values from the CRC and zlib specifications
+        assert_eq!(crc32(b"123456789".iter()), 0xcbf4_3926);
+        assert_eq!(crc32(b"IEND".iter()), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(&[]), 1);
+    }
+
+    #[test]
+    fn png_holds_the_pixels() {
+        let rendered = cube_render((160, 140));
+        let mut png = Vec::new();
+        rendered.write_png(&mut png).unwrap();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = be32(rest) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+            assert_eq!(be32(&rest[8 + len..]), crc32(rest[4..8 + len].iter()));
+            chunks.push((kind, data));
+            rest = &rest[12 + len..];
+        }
+        let kinds: Vec<_> = chunks.iter().map(|c| c.0).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        assert_eq!(chunks[0].1, [0, 0, 0, 160, 0, 0, 0, 140, 8, 2, 0, 0, 0]);
+
+        // zlib header, then stored deflate blocks until the final one
+        let zlib = chunks[1].1;
+        assert_eq!(((zlib[0] as u16) << 8 | zlib[1] as u16) % 31, 0);
+        let mut raw = Vec::new();
+        let mut at = 2;
+        let mut blocks = 0;
+        loop {
+            let last = zlib[at] == 1;
+            assert!(last || zlib[at] == 0);
+            let len = u16::from_le_bytes([zlib[at + 1], zlib[at + 2]]);
+            let nlen = u16::from_le_bytes([zlib[at + 3], zlib[at + 4]]);
+            assert_eq!(nlen, !len);
+            raw.extend_from_slice(&zlib[at + 5..at + 5 + len as usize]);
+            at += 5 + len as usize;
+            blocks += 1;
+            if last {
+                break;
+            }
+        }
+        assert_eq!(blocks, 2);
+        assert_eq!(be32(&zlib[at..]), adler32(&raw));
+        assert_eq!(zlib.len(), at + 4);
+
+        let rows = rendered.rows();
+        assert_eq!(raw.len(), 140 * (1 + 160 * 3));
+        for (line, row) in raw.chunks(1 + 160 * 3).zip(rows) {
+            assert_eq!(line[0], 0);
+            let pixels: Vec<_> = line[1..].chunks(3).collect();
+            let expected: Vec<_> = row.iter().map(|c| match c {
+                Cell::Empty => [0, 0, 0],
+                Cell::Hit{color: Rgb(r, g, b), ..} => [*r, *g, *b],
+            }).collect();
+            assert_eq!(pixels, expected);
+        }
+        assert!(rows.iter().flatten().any(|c| *c != Cell::Empty));
+        assert!(rows.iter().flatten().any(|c| *c == Cell::Empty));
+    }
+
+    #[test]
+    fn ppm_holds_the_pixels() {
+        let rendered = cube_render((3, 2));
+        let mut ppm = Vec::new();
+        rendered.write_ppm(&mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(&ppm[11..], &rendered.pixels().2[..]);
+    }
+}
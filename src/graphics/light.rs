@@ -0,0 +1,84 @@
+use std::f64;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy)]
+pub enum LightKind {
+    /// Emits in every direction from a position.
+    Point(Point3),
+    /// Parallel rays travelling along a direction, like sunlight.
+    Directional(Vec3),
+    /// Lights every surface evenly regardless of its orientation.
+    Ambient,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Light {
+    pub kind: LightKind,
+    pub intensity: f64,
+    /// Trace shadow rays towards this light. Ignored for ambient lights.
+    pub shadows: bool,
+}
+
+impl Light {
+    pub fn point(pos: Point3, intensity: f64) -> Self {
+        Light { kind: LightKind::Point(pos), intensity, shadows: false }
+    }
+
+    pub fn directional(dir: Vec3, intensity: f64) -> Self {
+        Light {
+            kind: LightKind::Directional(dir.normalize()),
+            intensity,
+            shadows: false,
+        }
+    }
+
+    pub fn ambient(intensity: f64) -> Self {
+        Light { kind: LightKind::Ambient, intensity, shadows: false }
+    }
+
+    pub fn with_shadows(mut self, shadows: bool) -> Self {
+        self.shadows = shadows;
+        self
+    }
+
+    /// Unit vector from `p` towards the light, `None` for ambient lights.
+    pub fn dir_from(&self, p: &Point3) -> Option<Vec3> {
+        match self.kind {
+            LightKind::Point(pos) => Some(Vec3::from(pos - *p).normalize()),
+            LightKind::Directional(dir) => Some(dir * -1.),
+            LightKind::Ambient => None,
+        }
+    }
+
+    /// Distance from `p` to the light, infinite for directional and
+    /// ambient lights.
+    pub fn dist_from(&self, p: &Point3) -> f64 {
+        match self.kind {
+            LightKind::Point(pos) => Vec3::from(pos - *p).norm(),
+            _ => f64::INFINITY,
+        }
+    }
+
+    /// Lambertian contribution of this light at `p` on a surface with unit
+    /// normal `n`.
+    pub fn diffuse(&self, p: &Point3, n: &Vec3) -> f64 {
+        match self.dir_from(p) {
+            Some(l) => self.intensity * n.dot(&l).max(0.),
+            None => self.intensity,
+        }
+    }
+}
+
+impl Transformable for Light {
+    fn apply(&mut self, t: &Transform) -> &mut Self {
+        match self.kind {
+            LightKind::Point(ref mut pos) => { pos.apply(t); },
+            LightKind::Directional(ref mut dir) => {
+                *dir = dir.apply(t).normalize();
+            },
+            LightKind::Ambient => {},
+        }
+        self
+    }
+}
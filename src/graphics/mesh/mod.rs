@@ -0,0 +1,112 @@
+//! Loaders turning mesh files into `Object`s.
+
+pub mod obj;
+pub mod stl;
+
+use std::error::Error;
+use std::f64;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use termion::color::Rgb;
+
+use super::*;
+use super::scene::Object;
+
+/// Colour of polygons a file gives none.
+pub(crate) const DEFAULT_COLOR: Rgb = Rgb(200, 200, 200);
+
+/// The rest of a line split into words.
+pub(crate) type Words<'a, 'b> = &'a mut dyn Iterator<Item = &'b str>;
+
+/// Reads the next `N` words as numbers, leaving any others.
+pub(crate) fn numbers<const N: usize>(words: Words) -> Result<[f64; N], String> {
+    let mut r = [0.; N];
+    for (i, x) in r.iter_mut().enumerate() {
+        let w = words.next()
+            .ok_or_else(|| format!("expected {} numbers, found {}", N, i))?;
+        *x = w.parse()
+            .map_err(|_| format!("expected a number, found `{}`", w))?;
+    }
+    Ok(r)
+}
+
+pub(crate) fn no_more(words: Words) -> Result<(), String> {
+    match words.next() {
+        Some(w) => Err(format!("unexpected `{}`", w)),
+        None => Ok(()),
+    }
+}
+
+impl Object {
+    /// Uniformly scales and moves the object so that it sits centred in
+    /// `target`, as large as fits. Handy for meshes authored in arbitrary
+    /// units, e.g. to place them in front of the default `Camera`. Empty
+    /// objects and ones with unbounded polygons are left as they are.
+    pub fn fit_to_box(&mut self, target: [Point3; 2]) -> &mut Self {
+        let (x0, y0, z0) = self.bounds[0].unpack();
+        let (x1, y1, z1) = self.bounds[1].unpack();
+        let finite = [x0, y0, z0, x1, y1, z1].iter().all(|x| x.is_finite());
+        if self.polygons.is_empty() || !finite {
+            return self;
+        }
+
+        let extent = (self.bounds[1] - self.bounds[0]).unpack();
+        let room = (target[1] - target[0]).unpack();
+        let scale = [(room.0, extent.0), (room.1, extent.1), (room.2, extent.2)]
+            .iter()
+            .filter(|&&(_, e)| e > 0.)
+            .map(|&(r, e)| r / e)
+            .fold(f64::INFINITY, f64::min);
+        let scale = if scale.is_finite() { scale } else { 1. };
+
+        let from: Vec3 = ((self.bounds[0] + self.bounds[1]) / 2.).into();
+        let to: Vec3 = ((target[0] + target[1]) / 2.).into();
+
+        let mut t = Transform::translate(from * -1.);
+        t
+            .apply(&Transform::scale(Vec3::new(scale, scale, scale)))
+            .apply(&Transform::translate(to));
+        self.apply(&t)
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io { path: PathBuf, err: io::Error },
+    /// `line` is 0 for files without lines, like binary STL.
+    Parse { path: PathBuf, line: usize, msg: String },
+}
+
+impl LoadError {
+    pub(crate) fn io(path: &Path, err: io::Error) -> Self {
+        LoadError::Io { path: path.to_owned(), err }
+    }
+
+    pub(crate) fn parse<S: Into<String>>(path: &Path, line: usize, msg: S) -> Self {
+        LoadError::Parse { path: path.to_owned(), line, msg: msg.into() }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io { path, err } =>
+                write!(f, "{}: {}", path.display(), err),
+            LoadError::Parse { path, line: 0, msg } =>
+                write!(f, "{}: {}", path.display(), msg),
+            LoadError::Parse { path, line, msg } =>
+                write!(f, "{}:{}: {}", path.display(), line, msg),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io { err, .. } => Some(err),
+            LoadError::Parse { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Wavefront OBJ meshes and their MTL material libraries.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use termion::color::Rgb;
+
+use super::{numbers, LoadError, DEFAULT_COLOR};
+use super::super::*;
+use super::super::scene::Object;
+
+impl Object {
+    /// Loads a Wavefront OBJ mesh.
+    ///
+    /// Faces with more than three vertices are fan-triangulated and
+    /// triangles without area are left out, vertex normals are averaged
+    /// into per-polygon shading normals, and `usemtl`
+    /// picks diffuse colours (`Kd`) from the `mtllib` files, or from an MTL
+    /// file next to `path` with the same name when there is no `mtllib`.
+    /// Without either, faces keep the default colour.
+    pub fn from_obj<P: AsRef<Path>>(path: P) -> Result<Object, LoadError> {
+        let path = path.as_ref();
+        let src = fs::read_to_string(path)
+            .map_err(|e| LoadError::io(path, e))?;
+        parse_obj(path, &src)
+    }
+}
+
+/// Resolves a 1-based (or negative, relative) OBJ index into `0..len`.
+fn index(s: &str, len: usize, what: &str) -> Result<usize, String> {
+    let i: i64 = s.parse()
+        .map_err(|_| format!("invalid {} index `{}`", what, s))?;
+    let r = if i > 0 { i - 1 } else { len as i64 + i };
+    if i == 0 || r < 0 || r >= len as i64 {
+        return Err(format!("{} index {} out of range ({} defined)", what, i, len));
+    }
+    Ok(r as usize)
+}
+
+/// Parses one `v`, `v/vt`, `v//vn` or `v/vt/vn` face element.
+fn face_vertex(s: &str, vs: usize, vns: usize)
+    -> Result<(usize, Option<usize>), String> {
+    let mut parts = s.split('/');
+    let v = index(parts.next().unwrap_or(""), vs, "vertex")?;
+    let _vt = parts.next();
+    let vn = match parts.next() {
+        Some(n) if !n.is_empty() => Some(index(n, vns, "normal")?),
+        _ => None,
+    };
+    if parts.next().is_some() {
+        return Err(format!("malformed face element `{}`", s));
+    }
+    Ok((v, vn))
+}
+
+fn parse_obj(path: &Path, src: &str) -> Result<Object, LoadError> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut vertices = Vec::new();
+    let mut vnormals = Vec::new();
+
+    let mut materials: Option<HashMap<String, Rgb>> = None;
+    let mut looked_next_to = false;
+    let mut current: Option<Rgb> = None;
+    let mut any_material = false;
+
+    let mut polygons = Vec::new();
+    let mut colors = Vec::new();
+    let mut normals: Vec<Option<Vec3>> = Vec::new();
+
+    for (i, line) in src.lines().enumerate() {
+        let line_no = i + 1;
+        let err = |msg: String| LoadError::parse(path, line_no, msg);
+
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(k) => k,
+            None => continue,
+        };
+
+        match keyword {
+            // anything after the coordinates, like a `w` or vertex
+            // colours, is left alone
+            "v" => {
+                let [x, y, z] = numbers::<3>(&mut words).map_err(err)?;
+                vertices.push(Point3::new(x, y, z));
+            },
+            "vn" => {
+                let [x, y, z] = numbers::<3>(&mut words).map_err(err)?;
+                vnormals.push(Vec3::new(x, y, z).normalize());
+            },
+            "f" => {
+                let refs = words
+                    .map(|w| face_vertex(w, vertices.len(), vnormals.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(err)?;
+                if refs.len() < 3 {
+                    return Err(err(format!(
+                        "face needs at least 3 vertices, found {}", refs.len())));
+                }
+
+                for k in 1..refs.len() - 1 {
+                    let tri = [refs[0], refs[k], refs[k + 1]];
+                    let (a, b, c) = (vertices[tri[0].0], vertices[tri[1].0], vertices[tri[2].0]);
+                    // triangles without area can't be hit and have no
+                    // normal to shade with
+                    if Vec3::from((b - a).cross_product(c - a)).norm() == 0. {
+                        continue;
+                    }
+                    polygons.push(Polygon::Triangle(a, b, c));
+                    colors.push(current.unwrap_or(DEFAULT_COLOR));
+                    normals.push(
+                        match (tri[0].1, tri[1].1, tri[2].1) {
+                            (Some(a), Some(b), Some(c)) => Some(
+                                (vnormals[a] + vnormals[b] + vnormals[c])
+                                    .normalize()
+                            ),
+                            _ => None,
+                        }
+                    );
+                }
+            },
+            "mtllib" => {
+                let libs = materials.get_or_insert_with(HashMap::new);
+                for name in words {
+                    libs.extend(load_mtl(&dir.join(name))?);
+                }
+            },
+            "usemtl" => {
+                let name = words.next()
+                    .ok_or_else(|| err("usemtl without a name".to_owned()))?;
+                if materials.is_none() && !looked_next_to {
+                    looked_next_to = true;
+                    let mtl = path.with_extension("mtl");
+                    if mtl.is_file() {
+                        materials = Some(load_mtl(&mtl)?);
+                    }
+                }
+                current = Some(match materials {
+                    Some(ref libs) => *libs.get(name)
+                        .ok_or_else(|| err(format!("unknown material `{}`", name)))?,
+                    // nowhere to look the material up
+                    None => DEFAULT_COLOR,
+                });
+                any_material = true;
+            },
+            // texture coordinates, groups and smoothing
+            // don't mean anything to this renderer
+            _ => {},
+        }
+    }
+
+    let mut obj = Object::new(DEFAULT_COLOR, polygons);
+    if any_material {
+        obj.colors = colors;
+    }
+    if normals.iter().any(|n| n.is_some()) {
+        obj.normals = normals.into_iter().zip(obj.polygons.iter())
+            .map(|(n, poly)| n.or_else(|| poly.normal()).unwrap())
+            .collect();
+    }
+    Ok(obj)
+}
+
+/// Reads the diffuse colour of every material in an MTL file.
+fn load_mtl(path: &Path) -> Result<HashMap<String, Rgb>, LoadError> {
+    let src = fs::read_to_string(path).map_err(|e| LoadError::io(path, e))?;
+
+    let mut materials = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for (i, line) in src.lines().enumerate() {
+        let line_no = i + 1;
+        let err = |msg: String| LoadError::parse(path, line_no, msg);
+
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("newmtl") => {
+                let name = words.next()
+                    .ok_or_else(|| err("newmtl without a name".to_owned()))?;
+                materials.insert(name.to_owned(), DEFAULT_COLOR);
+                current = Some(name.to_owned());
+            },
+            Some("Kd") => {
+                let name = current.as_ref()
+                    .ok_or_else(|| err("Kd before any newmtl".to_owned()))?;
+                let [r, g, b] = numbers::<3>(&mut words).map_err(err)?;
+                let channel = |x: f64| (x.clamp(0., 1.) * 255.).round() as u8;
+                materials.insert(name.clone(), Rgb(channel(r), channel(g), channel(b)));
+            },
+            _ => {},
+        }
+    }
+
+    Ok(materials)
+}
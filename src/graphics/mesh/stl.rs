@@ -0,0 +1,132 @@
+//! STL meshes, both the ASCII and the binary flavours.
+
+use std::fs;
+use std::path::Path;
+use std::str;
+
+use super::{no_more, numbers, LoadError, DEFAULT_COLOR};
+use super::super::*;
+use super::super::scene::Object;
+
+const HEADER_LEN: usize = 80;
+const FACET_LEN: usize = 50;
+
+impl Object {
+    /// Loads an STL mesh, telling ASCII and binary files apart by content.
+    /// Facet normals stored in the file are ignored in favour of the
+    /// geometric ones, as exporters often leave them zeroed.
+    pub fn from_stl<P: AsRef<Path>>(path: P) -> Result<Object, LoadError> {
+        let path = path.as_ref();
+        let data = fs::read(path).map_err(|e| LoadError::io(path, e))?;
+
+        let polygons = if is_binary(&data) {
+            parse_binary(&data).map_err(|msg| LoadError::parse(path, 0, msg))?
+        } else {
+            let src = str::from_utf8(&data).map_err(|e| {
+                let valid = &data[..e.valid_up_to()];
+                let line = valid.iter().filter(|&&b| b == b'\n').count() + 1;
+                LoadError::parse(path, line, "neither a binary STL nor UTF-8 text")
+            })?;
+            parse_ascii(path, src)?
+        };
+
+        Ok(Object::new(DEFAULT_COLOR, polygons))
+    }
+}
+
+/// Facet count of a binary file, from just after the header.
+fn facet_count(data: &[u8]) -> Option<usize> {
+    let mut count = [0; 4];
+    count.copy_from_slice(data.get(HEADER_LEN..HEADER_LEN + 4)?);
+    Some(u32::from_le_bytes(count) as usize)
+}
+
+/// Bytes a binary file with `count` facets needs, some exporters pad
+/// files past it.
+fn binary_len(count: usize) -> Option<usize> {
+    count.checked_mul(FACET_LEN)?.checked_add(HEADER_LEN + 4)
+}
+
+/// ASCII files start with `solid`, but binary ones may too, so those still
+/// count as binary when they have room for the facets they claim. The text
+/// where an ASCII file's count would be makes for billions of facets.
+fn is_binary(data: &[u8]) -> bool {
+    if !data.starts_with(b"solid") {
+        return true;
+    }
+    facet_count(data).and_then(binary_len).is_some_and(|len| data.len() >= len)
+}
+
+fn parse_binary(data: &[u8]) -> Result<Vec<Polygon>, String> {
+    let count = facet_count(data).ok_or_else(|| format!(
+        "binary STL needs at least {} bytes, found {}", HEADER_LEN + 4, data.len()))?;
+    match binary_len(count) {
+        Some(len) if data.len() >= len => {},
+        _ => return Err(format!(
+            "binary STL with {} facets is cut short at {} bytes", count, data.len())),
+    }
+
+    let f32_at = |at: usize| {
+        let mut b = [0; 4];
+        b.copy_from_slice(&data[at..at + 4]);
+        f32::from_le_bytes(b) as f64
+    };
+    let point_at = |at: usize| {
+        Point3::new(f32_at(at), f32_at(at + 4), f32_at(at + 8))
+    };
+
+    Ok((0..count)
+        .map(|i| {
+            // skip the 12 byte normal, the 2 trailing bytes are attributes
+            let at = HEADER_LEN + 4 + i * FACET_LEN + 12;
+            Polygon::Triangle(point_at(at), point_at(at + 12), point_at(at + 24))
+        })
+        .collect())
+}
+
+fn parse_ascii(path: &Path, src: &str) -> Result<Vec<Polygon>, LoadError> {
+    let mut polygons = Vec::new();
+    let mut facet: Vec<Point3> = Vec::with_capacity(3);
+    let mut in_facet = false;
+
+    for (i, line) in src.lines().enumerate() {
+        let line_no = i + 1;
+        let err = |msg: String| LoadError::parse(path, line_no, msg);
+
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("facet") => {
+                if in_facet {
+                    return Err(err("facet inside another facet".to_owned()));
+                }
+                in_facet = true;
+                facet.clear();
+            },
+            Some("vertex") => {
+                if !in_facet {
+                    return Err(err("vertex outside of a facet".to_owned()));
+                }
+                let [x, y, z] = numbers::<3>(&mut words).map_err(err)?;
+                no_more(&mut words).map_err(err)?;
+                facet.push(Point3::new(x, y, z));
+            },
+            Some("endfacet") => {
+                if facet.len() != 3 {
+                    return Err(err(format!(
+                        "facet needs 3 vertices, found {}", facet.len())));
+                }
+                polygons.push(Polygon::Triangle(facet[0], facet[1], facet[2]));
+                in_facet = false;
+            },
+            Some("solid") | Some("endsolid") |
+            Some("outer") | Some("endloop") | None => {},
+            Some(w) => return Err(err(format!("unexpected `{}`", w))),
+        }
+    }
+
+    if in_facet {
+        return Err(LoadError::parse(path, src.lines().count(),
+                                    "unterminated facet"));
+    }
+    Ok(polygons)
+}
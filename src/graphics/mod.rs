@@ -1,28 +1,32 @@
 
+pub mod bvh;
 pub mod camera;
+pub mod display;
+pub mod image;
+pub mod light;
+pub mod mesh;
+pub mod plane;
 pub mod scene;
+pub mod scene_file;
+pub mod shape;
+pub mod sphere;
 
-use ndarray::linalg::*;
+use std::sync::{Arc, RwLock};
 
-use ndarray::{
-    Array,
-    Array1,
-    Array2,
-    ArrayBase,
-};
+use ndarray::Array1;
 
-use std::cell::RefCell;
-use std::rc::Rc;
 
+/// Homogeneous point, `w` is always `1.`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point3([f64; 4]);
 
-#[derive(Debug, Clone)]
-pub struct Point3(Array1<f64>);
-
-#[derive(Debug, Clone)]
-pub struct Vec3(Array1<f64>);
+/// Homogeneous direction, `w` is always `0.`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vec3([f64; 4]);
 
-#[derive(Debug, Clone)]
-pub struct Transform(pub Array2<f64>);
+/// Row-major 4x4 affine matrix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform(pub [[f64; 4]; 4]);
 
 #[derive(Debug, Clone)]
 pub struct Ray3 {
@@ -30,25 +34,53 @@ pub struct Ray3 {
     vec: Vec3,
     inv_vec: (f64, f64, f64),
     inv_vec_sign: (u8, u8, u8),
+    vec_norm: f64,
 }
 
 pub enum Polygon {
     Triangle(Point3, Point3, Point3),
+    Sphere(sphere::Sphere),
+    Plane(plane::Plane),
+    Disc(plane::Disc),
 }
 
 pub trait Transformable {
     fn apply(&mut self, t: &Transform) -> &mut Self;
 }
 
+/// Where a ray meets a surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub point: Point3,
+    /// Distance from the ray origin.
+    pub dist: f64,
+    /// Unit geometric normal, turned to face back towards the ray origin.
+    pub normal: Vec3,
+    /// Whether the ray came from the side the surface's own normal points
+    /// to: the winding side of triangles, the outside of spheres.
+    pub front: bool,
+    /// Surface coordinates of the point. Barycentric for triangles, the
+    /// point being `(1-u-v)*p0 + u*p1 + v*p2`, multiples of the spanning
+    /// vectors for planes and discs, and longitude and latitude scaled to
+    /// `0..1` for spheres.
+    pub u: f64,
+    pub v: f64,
+    /// Index of the polygon hit within its object.
+    pub poly: usize,
+    /// Index of the object hit within `Scene::objects`.
+    pub object: usize,
+}
+
 pub trait Intersectable {
-    fn intersect(&self, ray: &Ray3) -> Option<(Point3, f64)>;
+    /// Closest hit along the ray.
+    fn intersect(&self, ray: &Ray3) -> Option<Hit>;
 }
 
 #[derive(Debug)]
 pub struct CoordSys(Transform);
 
 #[derive(Debug)]
-pub struct SharedCoordSys(Rc<RefCell<CoordSys>>);
+pub struct SharedCoordSys(Arc<RwLock<CoordSys>>);
 
 #[derive(Debug)]
 pub struct SelfRelative<T: Transformable>(T, CoordSys);
@@ -56,6 +88,25 @@ pub struct SelfRelative<T: Transformable>(T, CoordSys);
 #[derive(Debug)]
 pub struct Relative<T: Transformable>(T, SharedCoordSys);
 
+impl Hit {
+    /// A hit at `point` along `ray` on a surface with the unit normal
+    /// `normal`, which is turned towards the ray origin. `poly` and
+    /// `object` are left at `0` for the containers to fill in.
+    pub fn new(ray: &Ray3, point: Point3, normal: Vec3, (u, v): (f64, f64)) -> Self {
+        let front = normal.dot(&ray.vec) <= 0.;
+        Hit {
+            point,
+            dist: Vec3::from(point - ray.origin).norm(),
+            normal: if front { normal } else { normal * -1. },
+            front,
+            u,
+            v,
+            poly: 0,
+            object: 0,
+        }
+    }
+}
+
 impl Ray3 {
     pub fn new(origin: Point3, vec: Vec3) -> Self {
         let iv = vec.unpack_map(|x| 1./x);
@@ -66,11 +117,21 @@ impl Ray3 {
                 (iv.2 < 0.) as u8,
             ),
             inv_vec: iv,
+            vec_norm: vec.norm(),
             origin,
             vec,
         }
     }
 
+    pub fn origin(&self) -> Point3 {
+        self.origin
+    }
+
+    /// Direction of the ray, not normalised.
+    pub fn vec(&self) -> Vec3 {
+        self.vec
+    }
+
     fn map<F, R>(&self, f: F) -> (R, R, R)
         where F: Fn(u8, f64, f64, f64, u8) -> R
     {
@@ -102,10 +163,10 @@ impl Ray3 {
     /// Test for collision with an axis-aligned box
     // TODO: test p.unpack().0 vs p.uget(0) for performance
     pub fn collides_box(&self, bounds: &[Point3; 2]) -> bool {
-        let tmin = self.map(|i, o, v, iv, ivs|{
+        let tmin = self.map(|i, o, _v, iv, ivs|{
             ( unsafe { bounds[ivs as usize].uget(i) } - o) * iv
         });
-        let tmax = self.map(|i, o, v, iv, ivs|{
+        let tmax = self.map(|i, o, _v, iv, ivs|{
             ( unsafe { bounds[(1-ivs) as usize].uget(i) } - o) * iv
         });
 
@@ -123,49 +184,60 @@ impl Ray3 {
         //const t0: f64 = -1.;
         //const t1: f64 =  1.;
 
-        return tmax >= tmin;
+        tmax >= tmin
         //return  tmax > t0 && t1 > tmin;
 
     }
+
+    /// Distance from the origin at which the ray enters an axis-aligned
+    /// box, `0.` when it starts inside. Unlike `collides_box`, boxes behind
+    /// the origin are rejected.
+    pub fn box_dist(&self, bounds: &[Point3; 2]) -> Option<f64> {
+        let tmin = self.map(|i, o, _v, iv, ivs|{
+            ( unsafe { bounds[ivs as usize].uget(i) } - o) * iv
+        });
+        let tmax = self.map(|i, o, _v, iv, ivs|{
+            ( unsafe { bounds[(1-ivs) as usize].uget(i) } - o) * iv
+        });
+
+        let tmin = tmin.0.max(tmin.1).max(tmin.2).max(0.);
+        let tmax = tmax.0.min(tmax.1).min(tmax.2);
+
+        if tmax >= tmin {
+            Some(tmin * self.vec_norm)
+        } else {
+            None
+        }
+    }
 }
 
 impl Point3 {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
-        Point3(array![x, y, z, 1.])
+        Point3([x, y, z, 1.])
     }
 
+    /// # Safety
+    /// `i` must be less than 4.
     pub unsafe fn uget(&self, i: u8) -> f64 {
-        unsafe { *self.0.uget(i as usize) }
+        unsafe { *self.0.get_unchecked(i as usize) }
     }
 
+    /// # Safety
+    /// The returned pointers alias `self` and must not outlive it.
     pub unsafe fn unpack_ptr_mut(&mut self) ->
         (*mut f64, *mut f64, *mut f64) {
-        (
-            self.0.uget_mut(0),
-            self.0.uget_mut(1),
-            self.0.uget_mut(2),
-        )
+        let p = self.0.as_mut_ptr();
+        (p, p.add(1), p.add(2))
     }
 
+    #[inline]
     pub fn unpack(&self) -> (f64, f64, f64) {
-        unsafe {
-            (
-                *self.0.uget(0),
-                *self.0.uget(1),
-                *self.0.uget(2),
-            )
-        }
+        (self.0[0], self.0[1], self.0[2])
     }
 
     pub fn unpack_map<F>(&self, f: F)
         -> (f64, f64, f64) where F: Fn(f64) -> f64 {
-        unsafe {
-            (
-                f(*self.0.uget(0)),
-                f(*self.0.uget(1)),
-                f(*self.0.uget(2)),
-            )
-        }
+        (f(self.0[0]), f(self.0[1]), f(self.0[2]))
     }
 
     pub fn cross_product(&self, mut b: Self) -> Self {
@@ -173,26 +245,24 @@ impl Point3 {
 
         // assert!(a.len() == 3 || a.len() == 4);
 
-        unsafe {
-            let ax = *self.0.uget(0);
-            let ay = *self.0.uget(1);
-            let az = *self.0.uget(2);
-
-            let bx = *b.0.uget(0);
-            let by = *b.0.uget(1);
-            let bz = *b.0.uget(2);
+        let (ax, ay, az) = self.unpack();
+        let (bx, by, bz) = b.unpack();
 
-            *b.0.uget_mut(0) = ay*bz - az*by;
-            *b.0.uget_mut(1) = az*bx - ax*bz;
-            *b.0.uget_mut(2) = ax*by - ay*bx;
-        }
+        b.0[0] = ay*bz - az*by;
+        b.0[1] = az*bx - ax*bz;
+        b.0[2] = ax*by - ay*bx;
 
         b
     }
 
+    /// Dot product of the xyz components, the homogeneous `w` is ignored.
+    /// Including it added 1 to every product of two points, a bias
+    /// `Polygon::intersect` used to carry.
     #[inline]
     pub fn dot(&self, b: &Self) -> f64 {
-        self.0.dot(&b.0)
+        let (ax, ay, az) = self.unpack();
+        let (bx, by, bz) = b.unpack();
+        ax*bx + ay*by + az*bz
     }
 
     #[inline]
@@ -224,41 +294,31 @@ impl Point3 {
 
 impl Vec3 {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
-        Vec3(array![x, y, z, 0.])
+        Vec3([x, y, z, 0.])
     }
 
+    /// # Safety
+    /// `i` must be less than 4.
     pub unsafe fn uget(&self, i: u8) -> f64 {
-        unsafe { *self.0.uget(i as usize) }
+        unsafe { *self.0.get_unchecked(i as usize) }
     }
 
+    /// # Safety
+    /// The returned pointers alias `self` and must not outlive it.
     pub unsafe fn unpack_ptr_mut(&mut self) ->
         (*mut f64, *mut f64, *mut f64) {
-        (
-            self.0.uget_mut(0),
-            self.0.uget_mut(1),
-            self.0.uget_mut(2),
-        )
+        let p = self.0.as_mut_ptr();
+        (p, p.add(1), p.add(2))
     }
 
+    #[inline]
     pub fn unpack(&self) -> (f64, f64, f64) {
-        unsafe {
-            (
-                *self.0.uget(0),
-                *self.0.uget(1),
-                *self.0.uget(2),
-            )
-        }
+        (self.0[0], self.0[1], self.0[2])
     }
 
     pub fn unpack_map<F>(&self, f: F)
         -> (f64, f64, f64) where F: Fn(f64) -> f64 {
-        unsafe {
-            (
-                f(*self.0.uget(0)),
-                f(*self.0.uget(1)),
-                f(*self.0.uget(2)),
-            )
-        }
+        (f(self.0[0]), f(self.0[1]), f(self.0[2]))
     }
 
     pub fn cross_product(&self, mut b: Self) -> Self {
@@ -266,60 +326,51 @@ impl Vec3 {
 
         // assert!(a.len() == 3 || a.len() == 4);
 
-        unsafe {
-            let ax = *self.0.uget(0);
-            let ay = *self.0.uget(1);
-            let az = *self.0.uget(2);
+        let (ax, ay, az) = self.unpack();
+        let (bx, by, bz) = b.unpack();
 
-            let bx = *b.0.uget(0);
-            let by = *b.0.uget(1);
-            let bz = *b.0.uget(2);
-
-            *b.0.uget_mut(0) = ay*bz - az*by;
-            *b.0.uget_mut(1) = az*bx - ax*bz;
-            *b.0.uget_mut(2) = ax*by - ay*bx;
-        }
+        b.0[0] = ay*bz - az*by;
+        b.0[1] = az*bx - ax*bz;
+        b.0[2] = ax*by - ay*bx;
 
         b
     }
 
     pub fn dot(&self, b: &Self) -> f64 {
-        self.0.dot(&b.0)
+        let (ax, ay, az) = self.unpack();
+        let (bx, by, bz) = b.unpack();
+        ax*bx + ay*by + az*bz
     }
 
     pub fn norm(&self) -> f64 {
-        unsafe {
-            f64::sqrt(self.0.uget(0).powf(2.) + self.0.uget(1).powf(2.) + self.0.uget(2).powf(2.))
-        }
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Self {
+        self / self.norm()
     }
 }
 
 impl From<Point3> for Vec3 {
     fn from(a: Point3) -> Vec3 {
         let mut r = Vec3(a.0);
-        unsafe {
-            *r.0.uget_mut(3) = 0.;
-        }
+        r.0[3] = 0.;
         r
     }
 }
 
-impl From<Vec3> for Point3 {
-    fn from(a: Vec3) -> Point3 {
-        let mut r = Point3(a.0);
-        unsafe {
-            *r.0.uget_mut(3) = 1.;
-        }
-        r
+/// Takes the first three elements as xyz, anything after them is dropped.
+impl From<Array1<f64>> for Vec3 {
+    fn from(a: Array1<f64>) -> Vec3 {
+        assert!(a.len() >= 3, "a vector needs 3 elements, found {}", a.len());
+        Vec3::new(a[0], a[1], a[2])
     }
 }
 
-impl From<Array1<f64>> for Vec3 {
-    fn from(a: Array1<f64>) -> Vec3 {
-        let mut r = Vec3(a);
-        unsafe {
-            *r.0.uget_mut(3) = 0.;
-        }
+impl From<Vec3> for Point3 {
+    fn from(a: Vec3) -> Point3 {
+        let mut r = Point3(a.0);
+        r.0[3] = 1.;
         r
     }
 }
@@ -329,49 +380,38 @@ macro_rules! impl_op {
 
 impl ::std::ops::$tr<$ty> for $ty {
     type Output = $ty;
+    #[inline]
     fn $f(mut self, rhs: $ty) -> $ty {
-        unsafe {
-            *self.0.uget_mut(0) = ::std::ops::$tr::$f(*self.0.uget(0), *rhs.0.uget(0));
-            *self.0.uget_mut(1) = ::std::ops::$tr::$f(*self.0.uget(1), *rhs.0.uget(1));
-            *self.0.uget_mut(2) = ::std::ops::$tr::$f(*self.0.uget(2), *rhs.0.uget(2));
-        }
+        self.0[0] = ::std::ops::$tr::$f(self.0[0], rhs.0[0]);
+        self.0[1] = ::std::ops::$tr::$f(self.0[1], rhs.0[1]);
+        self.0[2] = ::std::ops::$tr::$f(self.0[2], rhs.0[2]);
         self
     }
 }
 
 impl<'a> ::std::ops::$tr<&'a $ty> for $ty {
     type Output = $ty;
-    fn $f(mut self, rhs: &'a $ty) -> $ty {
-        unsafe {
-            *self.0.uget_mut(0) = ::std::ops::$tr::$f(*self.0.uget(0), *rhs.0.uget(0));
-            *self.0.uget_mut(1) = ::std::ops::$tr::$f(*self.0.uget(1), *rhs.0.uget(1));
-            *self.0.uget_mut(2) = ::std::ops::$tr::$f(*self.0.uget(2), *rhs.0.uget(2));
-        }
-        self
+    #[inline]
+    fn $f(self, rhs: &'a $ty) -> $ty {
+        ::std::ops::$tr::$f(self, *rhs)
     }
 }
 
 impl<'a> ::std::ops::$tr<$ty> for &'a $ty {
     type Output = $ty;
+    #[inline]
     fn $f(self, rhs: $ty) -> $ty {
-        let mut r = self.clone();
-        unsafe {
-            *r.0.uget_mut(0) = ::std::ops::$tr::$f(*r.0.uget(0), *rhs.0.uget(0));
-            *r.0.uget_mut(1) = ::std::ops::$tr::$f(*r.0.uget(1), *rhs.0.uget(1));
-            *r.0.uget_mut(2) = ::std::ops::$tr::$f(*r.0.uget(2), *rhs.0.uget(2));
-        }
-        r
+        ::std::ops::$tr::$f(*self, rhs)
     }
 }
 
 impl ::std::ops::$tr<f64> for $ty {
     type Output = $ty;
+    #[inline]
     fn $f(mut self, rhs: f64) -> $ty {
-        unsafe {
-            *self.0.uget_mut(0) = ::std::ops::$tr::$f(*self.0.uget(0), rhs);
-            *self.0.uget_mut(1) = ::std::ops::$tr::$f(*self.0.uget(1), rhs);
-            *self.0.uget_mut(2) = ::std::ops::$tr::$f(*self.0.uget(2), rhs);
-        }
+        self.0[0] = ::std::ops::$tr::$f(self.0[0], rhs);
+        self.0[1] = ::std::ops::$tr::$f(self.0[1], rhs);
+        self.0[2] = ::std::ops::$tr::$f(self.0[2], rhs);
         self
     }
 }
@@ -392,12 +432,12 @@ impl_op!(Div, div, Vec3);
 // origin-point distance func
 #[inline]
 pub fn dist(r: &Ray3, p: &Point3) -> f64 {
-    Vec3::from(p.0.clone() - r.origin.0.clone()).norm()
+    Vec3::from(*p - r.origin).norm()
 }
 
 impl Transform {
     pub fn id() -> Self {
-        Transform(array![
+        Transform([
             [ 1., 0., 0., 0. ],
             [ 0., 1., 0., 0. ],
             [ 0., 0., 1., 0. ],
@@ -407,7 +447,7 @@ impl Transform {
 
     pub fn pivot(t: f64, axis: Vec3, center: Point3) -> Self {
         let center: Vec3 = center.into();
-        let mut r = Self::translate(center.clone()*-1.);
+        let mut r = Self::translate(center*-1.);
         r
             .apply(&Self::rotate(t, axis))
             .apply(&Self::translate(center));
@@ -420,7 +460,7 @@ impl Transform {
         let st = t.sin();
         let cm = 1. - ct;
 
-        Transform(array![
+        Transform([
             [l*l * cm +     ct, m*l * cm - n * st, n*l * cm + m * st, 0.],
             [l*m * cm + n * st, m*m * cm +     ct, n*m * cm - l * st, 0.],
             [l*n * cm - m * st, m*n * cm + l * st, n*n * cm +     ct, 0.],
@@ -429,74 +469,133 @@ impl Transform {
 
     }
 
+    pub fn scale(factors: Vec3) -> Self {
+        let (x, y, z) = factors.unpack();
+        Transform([
+            [ x,  0., 0., 0. ],
+            [ 0., y,  0., 0. ],
+            [ 0., 0., z,  0. ],
+            [ 0., 0., 0., 1. ],
+        ])
+    }
+
     pub fn translate(ofs: Vec3) -> Self {
         let (x, y, z) = ofs.unpack();
-        Transform(array![
+        Transform([
             [ 1., 0., 0., x  ],
             [ 0., 1., 0., y  ],
             [ 0., 0., 1., z  ],
             [ 0., 0., 0., 1. ],
         ])
     }
+
+    /// Inverse of an affine transform, `None` when it flattens space.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.0;
+        // cofactors of the linear 3x3 part
+        let c = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        let cof = [
+            [ c(1, 2, 1, 2), -c(1, 2, 0, 2),  c(1, 2, 0, 1)],
+            [-c(0, 2, 1, 2),  c(0, 2, 0, 2), -c(0, 2, 0, 1)],
+            [ c(0, 1, 1, 2), -c(0, 1, 0, 2),  c(0, 1, 0, 1)],
+        ];
+        let det = m[0][0] * cof[0][0] + m[0][1] * cof[0][1] + m[0][2] * cof[0][2];
+        if det.abs() < 1e-12 {
+            return None;
+        }
+
+        let mut r = Transform::id();
+        for (i, row) in r.0.iter_mut().take(3).enumerate() {
+            for (j, x) in row.iter_mut().take(3).enumerate() {
+                *x = cof[j][i] / det;
+            }
+            row[3] = -(row[0] * m[0][3] + row[1] * m[1][3] + row[2] * m[2][3]);
+        }
+        Some(r)
+    }
+
+    /// Transposes the linear part, dropping the translation. Normals go
+    /// through the transpose of the inverse.
+    pub fn transpose_linear(&self) -> Self {
+        let m = &self.0;
+        let mut r = Transform::id();
+        for (i, row) in r.0.iter_mut().take(3).enumerate() {
+            for (j, x) in row.iter_mut().take(3).enumerate() {
+                *x = m[j][i];
+            }
+        }
+        r
+    }
 }
 
 
 
 impl Transformable for Transform {
+    /// Left-multiplies: `self = t * self`.
     fn apply(&mut self, t: &Transform) -> &mut Self {
-        general_mat_mul(1., &t.0, &(self.0.clone()),
-                        0., &mut self.0);
+        let a = &t.0;
+        let b = self.0;
+        for (i, row) in self.0.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = a[i][0]*b[0][j] + a[i][1]*b[1][j]
+                   + a[i][2]*b[2][j] + a[i][3]*b[3][j];
+            }
+        }
         self
     }
 }
 
+#[inline]
+fn mat_vec_mul(t: &Transform, v: &mut [f64; 4]) {
+    let m = &t.0;
+    let x = *v;
+    for (i, r) in v.iter_mut().enumerate() {
+        *r = m[i][0]*x[0] + m[i][1]*x[1] + m[i][2]*x[2] + m[i][3]*x[3];
+    }
+}
 
 impl Transformable for Point3 {
     fn apply(&mut self, t: &Transform) -> &mut Self {
-        general_mat_vec_mul(1., &t.0, &(self.0.clone()),
-                            0., &mut self.0);
+        mat_vec_mul(t, &mut self.0);
         self
     }
 }
 
-// pub fn norm(v: &Array1<f64>) -> f64 {
-//     unsafe {
-//         f64::sqrt(v.uget(0).powf(2.) + v.uget(1).powf(2.) + v.uget(2).powf(2.))
-//     }
-// }
-
-// fn cross_product(a: &Array1<f64>,  b: &Array1<f64>) -> Array1<f64> {
-//     let mut r = a.clone();
-// 
-//     assert!(a.len() == 3 || a.len() == 4);
-// 
-//     unsafe {
-//         let ax = a.uget(0);
-//         let ay = a.uget(1);
-//         let az = a.uget(2);
-// 
-//         let bx = b.uget(0);
-//         let by = b.uget(1);
-//         let bz = b.uget(2);
-// 
-//         *r.uget_mut(0) = ay*bz - az*by;
-//         *r.uget_mut(1) = az*bx - ax*bz;
-//         *r.uget_mut(2) = ax*by - ay*bx;
-//     }
-// 
-//     r
-// }
+impl Transformable for Vec3 {
+    fn apply(&mut self, t: &Transform) -> &mut Self {
+        mat_vec_mul(t, &mut self.0);
+        self
+    }
+}
 
 impl Polygon {
+    /// Unit geometric normal of flat polygons, oriented by the winding of
+    /// the vertices. `None` for curved ones.
+    pub fn normal(&self) -> Option<Vec3> {
+        match self {
+            Polygon::Triangle(p0, p1, p2) => Some(
+                Vec3::from((*p1 - *p0).cross_product(*p2 - *p0)).normalize()
+            ),
+            Polygon::Sphere(_) => None,
+            Polygon::Plane(p) => Some(p.normal()),
+            Polygon::Disc(d) => Some(d.normal()),
+        }
+    }
+
     #[inline]
     pub fn bounds(&self) -> [Point3; 2] {
         match self {
             Polygon::Triangle(p0, p1, p2) => {
                 [
-                    p0.lower_bound(p1.lower_bound(p2.clone())),
-                    p0.upper_bound(p1.upper_bound(p2.clone())),
+                    p0.lower_bound(p1.lower_bound(*p2)),
+                    p0.upper_bound(p1.upper_bound(*p2)),
                 ]
-            }
+            },
+            Polygon::Sphere(s) => s.bounds(),
+            Polygon::Plane(p) => p.bounds(),
+            Polygon::Disc(d) => d.bounds(),
         }
     }
 }
@@ -508,7 +607,10 @@ impl Transformable for  Polygon {
                 p0.apply(t);
                 p1.apply(t);
                 p2.apply(t);
-            }
+            },
+            Polygon::Sphere(s) => { s.apply(t); },
+            Polygon::Plane(p) => { p.apply(t); },
+            Polygon::Disc(d) => { d.apply(t); },
         }
         self
     }
@@ -516,19 +618,19 @@ impl Transformable for  Polygon {
 
 impl Intersectable for Polygon {
     // TODO: ray should be passed by immut reference
-    fn intersect(&self, ray: &Ray3) -> Option<(Point3, f64)> {
+    fn intersect(&self, ray: &Ray3) -> Option<Hit> {
         match self {
             Polygon::Triangle(p0, p1, p2) => {
-                let Ray3 { origin: rayO, vec: rayV, .. } = ray;
-                let rayV:  Point3 = rayV.clone().into();
-                let (p0, p1, p2) = (p0.clone(), p1.clone(), p2.clone());
+                let Ray3 { origin: ray_o, vec: ray_v, .. } = ray;
+                let ray_v:  Point3 = (*ray_v).into();
+                let (p0, p1, p2) = (*p0, *p1, *p2);
 
                 const EPSILON: f64 = 0.0000001;
 
-                let edge1 = p1 - &p0;
-                let edge2 = p2 - &p0;
+                let edge1 = p1 - p0;
+                let edge2 = p2 - p0;
 
-                let h = edge2.cross_product(rayV.clone());
+                let h = edge2.cross_product(ray_v);
                 let a = edge1.dot(&h);
 
                 if a > -EPSILON && a < EPSILON {
@@ -536,15 +638,15 @@ impl Intersectable for Polygon {
                 }
 
                 let f = 1./a;
-                let s = rayO.clone() - &p0;
+                let s = *ray_o - p0;
                 let u = f * (s.dot(&h));
 
-                if u < 0.0 || u > 1.0 {
+                if !(0.0..=1.0).contains(&u) {
                     return None;
                 }
 
                 let q = edge1.cross_product(s); // s could be mut movd here
-                let v = f * rayV.dot(&q);
+                let v = f * ray_v.dot(&q);
 
                 if v < 0.0 || u + v > 1.0 {
                     return None;
@@ -553,12 +655,16 @@ impl Intersectable for Polygon {
                 let t = f * edge2.dot(&q);
 
                 if t > EPSILON {
-                    let p = rayO + rayV * t;
-                    return Some((p.clone(), Vec3::from(rayO - p).norm()));
+                    let p = ray_o + ray_v * t;
+                    let n = Vec3::from(edge1.cross_product(edge2)).normalize();
+                    Some(Hit::new(ray, p, n, (u, v)))
                 } else {
-                    return None;
+                    None
                 }
             },
+            Polygon::Sphere(s) => s.intersect(ray),
+            Polygon::Plane(p) => p.intersect(ray),
+            Polygon::Disc(d) => d.intersect(ray),
         }
     }
 
@@ -580,6 +686,10 @@ impl CoordSys {
     }
 }
 
+impl Default for CoordSys {
+    fn default() -> Self { Self::new() }
+}
+
 impl Transformable for CoordSys {
     fn apply(&mut self, t: &Transform) -> &mut Self {
         self.0.apply(t);
@@ -589,29 +699,33 @@ impl Transformable for CoordSys {
 
 impl SharedCoordSys {
     pub fn new() -> Self {
-        SharedCoordSys(Rc::new(RefCell::new(CoordSys::new())))
+        SharedCoordSys(Arc::new(RwLock::new(CoordSys::new())))
     }
 
-    pub fn apply_to<T: Transformable>(&self, mut x: T) -> T {
-        self.0.borrow().apply_to(x)
+    pub fn apply_to<T: Transformable>(&self, x: T) -> T {
+        self.0.read().unwrap().apply_to(x)
     }
 
     pub fn apply_rel(&mut self, t: Transform) -> &mut Self {
-        self.0.borrow_mut().apply_rel(t);
+        self.0.write().unwrap().apply_rel(t);
         self
     }
 }
 
+impl Default for SharedCoordSys {
+    fn default() -> Self { Self::new() }
+}
+
 impl Transformable for SharedCoordSys {
     fn apply(&mut self, t: &Transform) -> &mut Self {
-        self.0.borrow_mut().apply(t);
+        self.0.write().unwrap().apply(t);
         self
     }
 }
 
 impl Clone for SharedCoordSys {
     fn clone(&self) -> Self {
-        SharedCoordSys(Rc::clone(&self.0))
+        SharedCoordSys(Arc::clone(&self.0))
     }
 }
 
@@ -645,7 +759,7 @@ impl<T: Transformable> Transformable for SelfRelative<T> {
 }
 
 impl<T: Transformable + Intersectable + Clone> Intersectable for SelfRelative<T> {
-    fn intersect(&self, ray: &Ray3) -> Option<(Point3, f64)> {
+    fn intersect(&self, ray: &Ray3) -> Option<Hit> {
         self.get_abs().intersect(ray)
     }
 }
@@ -684,7 +798,7 @@ impl<T: Transformable> Transformable for Relative<T> {
 }
 
 impl<T: Transformable + Intersectable + Clone> Intersectable for Relative<T> {
-    fn intersect(&self, ray: &Ray3) -> Option<(Point3, f64)> {
+    fn intersect(&self, ray: &Ray3) -> Option<Hit> {
         self.get_abs().intersect(ray)
     }
 }
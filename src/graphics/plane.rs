@@ -0,0 +1,144 @@
+//! Flat primitives that aren't made of triangles: infinite planes and
+//! discs.
+//!
+//! Both are kept as a point and two vectors spanning the surface, which
+//! stay exact under any affine transform, so a scaled disc becomes an
+//! ellipse.
+
+use std::f64;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Plane {
+    origin: Point3,
+    u: Vec3,
+    v: Vec3,
+}
+
+/// Everything within one `u` and `v` of the center, the two radii.
+#[derive(Debug, Clone, Copy)]
+pub struct Disc {
+    center: Point3,
+    u: Vec3,
+    v: Vec3,
+}
+
+/// Unit vectors `u` and `v` perpendicular to `n` with `u × v = n`.
+fn tangents(n: Vec3) -> (Vec3, Vec3) {
+    let n = n.normalize();
+    let (x, y, z) = n.unpack();
+    // cross with the axis least aligned with the normal
+    let a = if x.abs() <= y.abs() && x.abs() <= z.abs() {
+        Vec3::new(1., 0., 0.)
+    } else if y.abs() <= z.abs() {
+        Vec3::new(0., 1., 0.)
+    } else {
+        Vec3::new(0., 0., 1.)
+    };
+    let u = n.cross_product(a).normalize();
+    (u, n.cross_product(u))
+}
+
+/// Where the ray crosses the plane through `o` with normal `n`, as the ray
+/// parameter and the point.
+fn hit_plane(o: &Point3, n: &Vec3, ray: &Ray3) -> Option<(f64, Point3)> {
+    const EPSILON: f64 = 0.0000001;
+
+    let denom = n.dot(&ray.vec);
+    if denom.abs() < EPSILON {
+        return None;
+    }
+    let t = n.dot(&Vec3::from(*o - ray.origin)) / denom;
+    if t <= EPSILON {
+        return None;
+    }
+    Some((t, ray.origin + Point3::from(ray.vec * t)))
+}
+
+/// `d` as `a*u + b*v` for `d` in the plane of `u` and `v`, which needn't
+/// be perpendicular once sheared.
+fn coords(d: Vec3, u: &Vec3, v: &Vec3) -> (f64, f64) {
+    let (uu, uv, vv) = (u.dot(u), u.dot(v), v.dot(v));
+    let (du, dv) = (d.dot(u), d.dot(v));
+    let det = uu * vv - uv * uv;
+    ((du * vv - dv * uv) / det, (dv * uu - du * uv) / det)
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3) -> Self {
+        let (u, v) = tangents(normal);
+        Plane { origin: point, u, v }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.u.cross_product(self.v).normalize()
+    }
+
+    /// Always infinite, see `Bvh` for how unbounded items are culled.
+    pub fn bounds(&self) -> [Point3; 2] {
+        [
+            Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        ]
+    }
+}
+
+impl Disc {
+    pub fn new(center: Point3, normal: Vec3, radius: f64) -> Self {
+        let (u, v) = tangents(normal);
+        Disc { center, u: u * radius, v: v * radius }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.u.cross_product(self.v).normalize()
+    }
+
+    /// Tight axis-aligned bounds of the (possibly elliptical) disc.
+    pub fn bounds(&self) -> [Point3; 2] {
+        let (ux, uy, uz) = self.u.unpack();
+        let (vx, vy, vz) = self.v.unpack();
+        let half = |a: f64, b: f64| (a * a + b * b).sqrt();
+        let half = Point3::new(half(ux, vx), half(uy, vy), half(uz, vz));
+        [self.center - half, self.center + half]
+    }
+}
+
+impl Transformable for Plane {
+    fn apply(&mut self, t: &Transform) -> &mut Self {
+        self.origin.apply(t);
+        self.u.apply(t);
+        self.v.apply(t);
+        self
+    }
+}
+
+impl Transformable for Disc {
+    fn apply(&mut self, t: &Transform) -> &mut Self {
+        self.center.apply(t);
+        self.u.apply(t);
+        self.v.apply(t);
+        self
+    }
+}
+
+impl Intersectable for Plane {
+    fn intersect(&self, ray: &Ray3) -> Option<Hit> {
+        let n = self.normal();
+        let (_, p) = hit_plane(&self.origin, &n, ray)?;
+        let uv = coords(Vec3::from(p - self.origin), &self.u, &self.v);
+        Some(Hit::new(ray, p, n, uv))
+    }
+}
+
+impl Intersectable for Disc {
+    fn intersect(&self, ray: &Ray3) -> Option<Hit> {
+        let n = self.normal();
+        let (_, p) = hit_plane(&self.center, &n, ray)?;
+        let (a, b) = coords(Vec3::from(p - self.center), &self.u, &self.v);
+        if a * a + b * b > 1. {
+            return None;
+        }
+        Some(Hit::new(ray, p, n, (a, b)))
+    }
+}
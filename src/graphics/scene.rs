@@ -2,31 +2,73 @@ use std::default::Default;
 use std::cmp::Ordering;
 
 use super::*;
+use super::bvh::Bvh;
 use super::camera::Camera;
+use super::display::{ColorMode, Pen, Ramp, Style};
+use super::light::Light;
+use super::shape::Shape;
 
 use termion::color::*;
 use termion::cursor;
 
 use std::f64;
+use std::sync::Mutex;
+use std::thread;
 
 pub struct Object {
     pub bounds: [Point3; 2],
     pub polygons: Vec<Polygon>,
     pub color: Rgb,
+    /// Per-polygon colours overriding `color`, empty to use `color` for all.
+    pub colors: Vec<Rgb>,
+    /// Per-polygon shading normals, empty to use the geometric normals.
+    /// Curved polygons always use their own.
+    pub normals: Vec<Vec3>,
+    bvh: Bvh,
 }
 
 pub struct Scene {
-    pub objects: Vec<Object>,
+    /// See `objects` and `objects_mut`, which keep track of whether
+    /// `bvh` is still up to date.
+    objects: Vec<Box<dyn Shape>>,
     pub camera: Camera,
+    /// With no lights, cells are shaded by distance alone.
+    pub lights: Vec<Light>,
+    /// How far shadow rays start off the surface along its normal, so a
+    /// polygon doesn't shadow itself through rounding errors.
+    pub shadow_bias: f64,
+    /// Worker threads used by `render`, `1` renders on the calling thread.
+    pub threads: usize,
+    bvh: Bvh,
+    /// Objects may have been added or moved since `bvh` was fitted.
+    stale: bool,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Cell {
     Empty,
-    Hit{ color: Rgb, dist: f64 },
+    /// `color` is already shaded by `light`, the total
+    /// light intensity reaching the hit point.
+    Hit{ color: Rgb, dist: f64, normal: Vec3, light: f64 },
 }
 
-pub struct RenderedScene(Vec<Vec<Cell>>);
+/// What a ray from the camera hit, for telling what's under a cell.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pick {
+    /// Index of the object within `Scene::objects`.
+    pub object: usize,
+    /// Index of the part of the object, see `Hit::poly`.
+    pub poly: usize,
+    pub point: Point3,
+    pub dist: f64,
+}
+
+/// The cells of a render, along with what was hit in each so that
+/// picking after a render doesn't cast any rays.
+pub struct RenderedScene {
+    cells: Vec<Vec<Cell>>,
+    picks: Vec<Vec<Option<Pick>>>,
+}
 
 impl Object {
     pub fn new(color: Rgb, polygons: Vec<Polygon>) -> Self {
@@ -34,34 +76,76 @@ impl Object {
             bounds: [Point3::new(0.,0.,0.), Point3::new(0.,0.,0.)],
             polygons,
             color,
+            colors: Vec::new(),
+            normals: Vec::new(),
+            bvh: Bvh::new(),
         };
         r.recalc_bounds();
         r
     }
 
+    /// Axis-aligned box spanning `min` to `max`, two triangles per face.
+    pub fn cube(color: Rgb, min: Point3, max: Point3) -> Self {
+        let (x0, y0, z0) = min.unpack();
+        let (x1, y1, z1) = max.unpack();
+        // corner `i` takes the max along x, y and z for bits 1, 2 and 4
+        let c = |i: usize| Point3::new(
+            if i & 1 != 0 { x1 } else { x0 },
+            if i & 2 != 0 { y1 } else { y0 },
+            if i & 4 != 0 { z1 } else { z0 },
+        );
+        let faces = [
+            [4, 5, 7, 6], [0, 2, 3, 1], [0, 4, 6, 2],
+            [1, 3, 7, 5], [0, 1, 5, 4], [2, 6, 7, 3],
+        ];
+        Object::new(
+            color,
+            faces.iter().flat_map(|f| vec![
+                Polygon::Triangle(c(f[0]), c(f[1]), c(f[2])),
+                Polygon::Triangle(c(f[0]), c(f[2]), c(f[3])),
+            ]).collect(),
+        )
+    }
+
+    /// Recomputes `bounds` and brings the polygon hierarchy up to date.
+    /// Must be called after editing `polygons` directly.
     pub fn recalc_bounds(&mut self) {
-        self.bounds =
-            if self.polygons.len() > 0 {
-                let mut b = self.polygons.iter().map(|py| py.bounds());
-                let acc = b.next().unwrap();
-                b.fold(acc, |mut a, x| unsafe { [
-                    a.get_unchecked_mut(0).lower_bound(x.get_unchecked(0).clone()),
-                    a.get_unchecked_mut(1).upper_bound(x.get_unchecked(1).clone()),
-                ]})
-            } else {
-                [Point3::new(0.,0.,0.), Point3::new(0.,0.,0.)]
-            }
+        let bounds: Vec<_> = self.polygons.iter().map(|py| py.bounds()).collect();
+        self.bvh.update(&bounds);
+        self.bounds = self.bvh.bounds()
+            .unwrap_or_else(|| [Point3::new(0.,0.,0.), Point3::new(0.,0.,0.)]);
+    }
+
+    /// Colour of the polygon at index `poly`.
+    pub fn color_of(&self, poly: usize) -> Rgb {
+        self.colors.get(poly).cloned().unwrap_or(self.color)
+    }
+
+    /// Normal used for lighting at `hit`: the shading normal of the
+    /// polygon hit if it has one, turned the same way as `hit.normal`.
+    pub fn normal_at(&self, hit: &Hit) -> Vec3 {
+        match (self.normals.get(hit.poly), &self.polygons[hit.poly]) {
+            (Some(&sn), poly) if !matches!(poly, Polygon::Sphere(_)) =>
+                if sn.dot(&hit.normal) < 0. { sn * -1. } else { sn },
+            _ => hit.normal,
+        }
     }
 }
 
 impl Transformable for Object {
     fn apply(&mut self, t: &Transform) -> &mut Self {
         self.polygons.iter_mut().for_each(|p| { p.apply(t); });
-        // self.bounds[0].apply(t);
-        // self.bounds[1].apply(t);
-        // TODO: This should instead apply transform to bounds
-        // NOTE: actually, im not sure this
-        // would have the same effect
+        // normals go through the transpose of the inverse, like the
+        // sphere's, and mean nothing once space is flattened
+        match t.inverse() {
+            Some(inv) => {
+                let t = inv.transpose_linear();
+                self.normals.iter_mut().for_each(|n| { *n = n.apply(&t).normalize(); });
+            },
+            None => self.normals.clear(),
+        }
+        // polygons keep their place in the hierarchy,
+        // so this only refits the node bounds
         self.recalc_bounds();
         self
     }
@@ -69,56 +153,221 @@ impl Transformable for Object {
 
 #[inline]
 fn fake_cmp<T: PartialOrd>(a: &T, b: &T) -> Ordering {
-    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+impl Object {
+    /// Whether anything in the object lies on `ray` closer than `max_dist`.
+    pub fn occludes(&self, ray: &Ray3, max_dist: f64) -> bool {
+        let blocks = |poly: &Polygon| {
+            poly.intersect(ray).is_some_and(|hit| hit.dist < max_dist)
+        };
+        if self.bvh.len() != self.polygons.len() {
+            return self.polygons.iter().any(blocks);
+        }
+        self.bvh.any(ray, max_dist, |i| blocks(&self.polygons[i]))
+    }
 }
 
 impl Intersectable for Object {
-    fn intersect(&self, ray: &Ray3) -> Option<(Point3, f64)> {
-        // TODO: should do bounds check first here...
-
-        // perform bounds check before
-        // actual polygon intersections
-        if ray.collides_box(&self.bounds) {
-            self.polygons.iter().filter_map(|poly| poly.intersect(&ray))
-                .min_by(|a, b| fake_cmp(&a.1, &b.1))
+    fn intersect(&self, ray: &Ray3) -> Option<Hit> {
+        let with_index = |i: usize| {
+            self.polygons[i].intersect(ray).map(|hit| Hit { poly: i, ..hit })
+        };
+        if self.bvh.len() != self.polygons.len() {
+            // polygons were edited without recalc_bounds
+            (0..self.polygons.len()).filter_map(with_index)
+                .min_by(|a, b| fake_cmp(&a.dist, &b.dist))
         } else {
-            None
+            self.bvh.closest(ray, |i| with_index(i).map(|hit| (hit, hit.dist)))
+                .map(|(hit, _)| hit)
         }
     }
 }
 
+impl Default for Scene {
+    fn default() -> Self { Self::new() }
+}
+
 impl Scene {
     pub fn new() -> Self {
         Self {
             objects: Vec::new(),
             camera: Camera::default(),
+            lights: Vec::new(),
+            shadow_bias: 1e-3,
+            threads: thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1),
+            bvh: Bvh::new(),
+            stale: false,
+        }
+    }
+
+    /// Adds a shape and returns its index in `objects`.
+    pub fn add<S: Shape + 'static>(&mut self, shape: S) -> usize {
+        self.objects.push(Box::new(shape));
+        self.stale = true;
+        self.objects.len() - 1
+    }
+
+    pub fn objects(&self) -> &[Box<dyn Shape>] {
+        &self.objects
+    }
+
+    /// The objects for moving, adding or removing them. Until the next
+    /// `refit` or `render` queries test every object instead of going
+    /// through the hierarchy, so they see the changes.
+    pub fn objects_mut(&mut self) -> &mut Vec<Box<dyn Shape>> {
+        self.stale = true;
+        &mut self.objects
+    }
+
+    /// Brings the object hierarchy up to date with `objects`: a refit when
+    /// objects only moved, a rebuild when some were added or removed.
+    /// `render` does this itself.
+    pub fn refit(&mut self) {
+        let bounds: Vec<_> = self.objects.iter().map(|o| o.bounds()).collect();
+        self.bvh.update(&bounds);
+        self.stale = false;
+    }
+
+    /// Closest object along `ray` together with the hit, which has
+    /// `object` set to the index of the object.
+    pub fn closest_hit(&self, ray: &Ray3) -> Option<(&dyn Shape, Hit)> {
+        let with_index = |i: usize| {
+            let obj = &*self.objects[i];
+            obj.intersect(ray).map(|hit| (obj, Hit { object: i, ..hit }))
+        };
+        if !self.stale {
+            self.bvh.closest(ray, |i| with_index(i).map(|x| (x, x.1.dist)))
+                .map(|(x, _)| x)
+        } else {
+            // objects changed since the last refit
+            (0..self.objects.len()).filter_map(with_index)
+                .min_by(|a, b| fake_cmp(&a.1.dist, &b.1.dist))
+        }
+    }
+
+    /// Whether any object lies on `ray` closer than `max_dist`. Cheaper
+    /// than `closest_hit` as it stops at the first hit found.
+    pub fn any_hit(&self, ray: &Ray3, max_dist: f64) -> bool {
+        if !self.stale {
+            self.bvh.any(ray, max_dist, |i| self.objects[i].occludes(ray, max_dist))
+        } else {
+            self.objects.iter().any(|obj| obj.occludes(ray, max_dist))
+        }
+    }
+
+    /// Total light intensity reaching `p` on a surface facing `n`.
+    pub fn light_at(&self, p: &Point3, n: &Vec3, dist: f64) -> f64 {
+        if self.lights.is_empty() {
+            return ((50. - dist) / 50.).clamp(0., 1.);
+        }
+        self.lights.iter()
+            .map(|l| {
+                let diffuse = l.diffuse(p, n);
+                if diffuse > 0. && l.shadows && self.in_shadow(l, p, n) {
+                    0.
+                } else {
+                    diffuse
+                }
+            })
+            .sum::<f64>()
+            .min(1.)
+    }
+
+    fn in_shadow(&self, light: &Light, p: &Point3, n: &Vec3) -> bool {
+        match light.dir_from(p) {
+            Some(l) => {
+                let origin = *p + Point3::from(*n * self.shadow_bias);
+                let max_dist = light.dist_from(&origin);
+                self.any_hit(&Ray3::new(origin, l), max_dist)
+            },
+            None => false,
         }
     }
 
     pub fn test_ray(&self, cell: (usize, usize)) -> Cell {
-        self.camera.get_ray(cell)
-            .and_then(|ray| {
-                self.objects.iter()
-                    .filter_map(|obj|
-                        obj.intersect(&ray).map(|ip| (obj.color, ip))
-                    )
-                    .min_by(|a, b| fake_cmp(&(a.1).1, &(b.1).1))
-                    .map(|(c, (_, d))| Cell::Hit { color: c, dist: d })
+        self.trace(cell).0
+    }
 
-            }).unwrap_or(Cell::Empty)
+    /// What's under the cell at `(x, y)`, `None` when the ray from the
+    /// camera hits nothing or the cell is off screen. Use the picks of the
+    /// last render with `RenderedScene::pick` when that is what's shown.
+    pub fn pick(&self, cell: (usize, usize)) -> Option<Pick> {
+        self.camera_hit(cell).map(|(_, hit)| Pick::from(hit))
     }
 
-    pub fn render(&self, out: &mut RenderedScene) {
-        self.camera.get_screen_centers()
-            .indexed_iter()
-            .for_each(|((y, x), _)|{
-                out.0[y][x] = self.test_ray((x, y));
-            });
+    /// Closest hit on the ray from the camera through `cell`, within the
+    /// camera's far distance.
+    fn camera_hit(&self, cell: (usize, usize)) -> Option<(&dyn Shape, Hit)> {
+        let ray = self.camera.get_ray(cell)?;
+        self.closest_hit(&ray)
+            .filter(|(_, hit)| hit.dist <= self.camera.far_along(&ray))
+    }
+
+    fn trace(&self, cell: (usize, usize)) -> (Cell, Option<Pick>) {
+        self.camera_hit(cell)
+            .map(|(obj, hit)| {
+                let normal = obj.normal_at(&hit);
+                let light = self.light_at(&hit.point, &normal, hit.dist);
+                let cell = Cell::Hit {
+                    color: mul_rgb(obj.color_of(hit.poly), light),
+                    dist: hit.dist,
+                    normal,
+                    light,
+                };
+                (cell, Some(Pick::from(hit)))
+            }).unwrap_or((Cell::Empty, None))
+    }
+
+    pub fn render(&mut self, out: &mut RenderedScene) {
+        self.refit();
+
+        let scene = &*self;
+        let render_row = |y: usize, (row, picks): (&mut Vec<Cell>, &mut Vec<Option<Pick>>)| {
+            row.iter_mut().zip(picks.iter_mut()).enumerate()
+                .for_each(|(x, (cell, pick))| {
+                    let (c, p) = scene.trace((x, y));
+                    *cell = c;
+                    *pick = p;
+                });
+        };
+
+        let threads = self.threads.min(out.cells.len());
+        let rows = out.cells.iter_mut().zip(out.picks.iter_mut()).enumerate();
+        if threads <= 1 {
+            rows.for_each(|(y, row)| render_row(y, row));
+            return;
+        }
+
+        // workers pull rows one at a time so that rows crossing
+        // expensive geometry don't stall a whole band of the screen.
+        // They are spawned afresh each frame: that costs tens of
+        // microseconds against milliseconds of tracing, and scoped
+        // threads can borrow the scene where workers kept between
+        // frames would need it shared behind an `Arc` and a lock
+        let rows = Mutex::new(rows);
+        thread::scope(|s| {
+            for _ in 0..threads {
+                s.spawn(|| loop {
+                    let next = rows.lock().unwrap().next();
+                    match next {
+                        Some((y, row)) => render_row(y, row),
+                        None => break,
+                    }
+                });
+            }
+        });
     }
 
     pub fn empty_render(&self) -> RenderedScene {
-        let (x, y) = self.camera.get_screen_size().clone();
-        RenderedScene(vec![vec![Cell::Empty; x]; y])
+        let (x, y) = *self.camera.get_screen_size();
+        RenderedScene {
+            cells: vec![vec![Cell::Empty; x]; y],
+            picks: vec![vec![None; x]; y],
+        }
     }
 }
 
@@ -130,39 +379,94 @@ fn mul_rgb(Rgb(r, g, b): Rgb, n: f64) -> Rgb {
     )
 }
 
+impl From<Hit> for Pick {
+    fn from(hit: Hit) -> Self {
+        Pick { object: hit.object, poly: hit.poly, point: hit.point, dist: hit.dist }
+    }
+}
+
 impl RenderedScene {
+    pub fn rows(&self) -> &[Vec<Cell>] {
+        &self.cells
+    }
+
+    /// What was hit in the cell at `(x, y)` when this was rendered.
+    pub fn pick(&self, (x, y): (usize, usize)) -> Option<Pick> {
+        self.picks.get(y).and_then(|r| r.get(x)).cloned().flatten()
+    }
+
+    /// Blends the colour of the cells showing `object` halfway towards
+    /// `tint`, to mark it out e.g. as selected.
+    pub fn highlight(&mut self, object: usize, tint: Rgb) {
+        let Rgb(tr, tg, tb) = tint;
+        let mix = |a: u8, b: u8| ((a as u16 + b as u16) / 2) as u8;
+        for (row, picks) in self.cells.iter_mut().zip(&self.picks) {
+            for (cell, pick) in row.iter_mut().zip(picks) {
+                match (cell, pick) {
+                    (Cell::Hit{color: Rgb(r, g, b), ..}, Some(p)) if p.object == object => {
+                        *r = mix(*r, tr);
+                        *g = mix(*g, tg);
+                        *b = mix(*b, tb);
+                    },
+                    _ => {},
+                }
+            }
+        }
+    }
+
+    /// What's under the character at `(x, y)` of the output in `style`,
+    /// the closest of the hits in the cells it covers.
+    pub fn pick_glyph(&self, style: &Style, (x, y): (usize, usize)) -> Option<Pick> {
+        let (w, h) = style.cells_per_glyph();
+        (0..h).flat_map(|dy| (0..w).map(move |dx| (x * w + dx, y * h + dy)))
+            .filter_map(|cell| self.pick(cell))
+            .min_by(|a, b| fake_cmp(&a.dist, &b.dist))
+    }
+
+    /// Writes the whole scene at the cursor as half blocks with the
+    /// detected `ColorMode`, see `Display` for output that only redraws
+    /// what changed.
     pub fn display(&self, o: &mut impl ::std::io::Write) {
+        self.display_with(o, &Style::HalfBlock, ColorMode::detect())
+    }
+
+    pub fn display_with(
+        &self,
+        o: &mut impl ::std::io::Write,
+        style: &Style,
+        mode: ColorMode,
+
+        ) {
+
+        let mut pen = Pen::new(mode);
+        let (w, h) = style.glyphs_size(&self.cells);
         write!(o, "{}", cursor::Hide).unwrap();
-        self.0.chunks(2).for_each(|rows| {
-            match rows {
-                &[ref rowa, ref rowb] => {
-                    rowa.iter().zip(rowb.iter()).for_each(|(cella, cellb)| {
-                        let mut fg = None;
-                        let mut bg = None;
-                        match cella {
-                            Cell::Empty => {},
-                            Cell::Hit{color: c, dist: d} =>
-                                bg = Some(mul_rgb(*c, (50.-*d)/50.)),
-
-                        }
-                        match cellb {
-                            Cell::Empty => {},
-                            Cell::Hit{color: c, dist: d} =>
-                                fg = Some(mul_rgb(*c, (50.-*d)/50.)),
-                        }
-                        match (fg, bg) {
-                            (Some(fg), Some(bg)) => write!(o, "{}{}▄", Fg(fg), Bg(bg)),
-                            (Some(fg), None)     => write!(o, "{}{}▄", Fg(fg), Bg(Reset)),
-                            (None,     Some(bg)) => write!(o, "{}{}▀", Fg(bg), Bg(Reset)),
-                            (None,     None)     => write!(o, "{} ", Bg(Reset)),
-                        }.unwrap()
-                    });
-                },
-                _ => {},
+        for y in 0..h {
+            // no newline after the last line, it would scroll a full terminal
+            if y > 0 {
+                write!(o, "\n\r").unwrap();
             }
-
-            write!(o, "\n\r").unwrap();
-        });
+            for x in 0..w {
+                let g = mode.quantize(style.glyph(&self.cells, (x, y)));
+                pen.glyph(o, g).unwrap();
+            }
+        }
+        pen.reset(o).unwrap();
         write!(o, "{}", cursor::Show).unwrap();
     }
+
+    /// The scene as plain text through `ramp`, one line per row of cells
+    /// with trailing blanks trimmed, for pasting where escapes won't do.
+    pub fn to_text(&self, ramp: &Ramp) -> String {
+        let style = Style::Ramp { ramp: ramp.clone(), color: false };
+        let (w, h) = style.glyphs_size(&self.cells);
+        let mut text = String::new();
+        for y in 0..h {
+            let line: String = (0..w).map(|x| style.glyph(&self.cells, (x, y)).ch)
+                .collect();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
 }
@@ -0,0 +1,329 @@
+//! Line-based text format describing a whole `Scene`.
+//!
+//! ```text
+//! # comments run to the end of the line, angles are in degrees
+//! camera translate 0 0 10
+//! camera rotate 15 1 0 0            # pivots around Camera::get_pivot
+//! camera zoom 1.5                   # see Camera::zoom
+//! camera orthographic
+//!
+//! light ambient 0.2
+//! light directional -0.4 0.6 -1 0.8 shadows
+//! light point 15 0 -15 0.5
+//! shadow_bias 0.001
+//!
+//! object
+//!     color 55 155 255
+//!     mesh models/teapot.obj          # .obj or .stl, relative to this file
+//!     triangle 0 0 0  1 0 0  0 1 0
+//!     sphere 5 5 -5 2                 # centre and radius
+//!     disc 5 10 -5  0 1 0  3          # centre, normal and radius
+//!     plane 0 12 0  0 -1 0            # a point and the normal, endless
+//!     fit 10 10 -20  20 20 -10
+//!     scale 1 2 1
+//!     rotate 45 0 1 0                 # about the origin...
+//!     rotate 45 0 1 0  15 15 -15      # ...or about a centre
+//!     translate 0 0 -5
+//! end
+//! ```
+//!
+//! Object transforms apply in the order they are written, after all the
+//! geometry of the object has been gathered.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use termion::color::Rgb;
+
+use super::*;
+use super::camera::Projection;
+use super::light::Light;
+use super::mesh::{self, no_more, numbers, LoadError, Words};
+use super::scene::{Object, Scene};
+use super::plane::{Disc, Plane};
+use super::sphere::Sphere;
+
+enum Step {
+    Fit([Point3; 2]),
+    Transform(Transform),
+}
+
+struct ObjectDef {
+    line: usize,
+    color: Option<Rgb>,
+    meshes: Vec<Object>,
+    polygons: Vec<Polygon>,
+    steps: Vec<Step>,
+}
+
+impl Scene {
+    /// Reads a scene description, see the module docs for the format.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Scene, LoadError> {
+        let path = path.as_ref();
+        let src = fs::read_to_string(path)
+            .map_err(|e| LoadError::io(path, e))?;
+        parse_scene(path, &src)
+    }
+}
+
+/// The mesh files a scene file refers to, e.g. to notice when they change.
+/// Only `mesh` statements are looked at, without checking the rest of the
+/// file, and material libraries of OBJ meshes aren't included. A scene file
+/// that can't be read refers to none.
+pub fn mesh_files<P: AsRef<Path>>(path: P) -> Vec<PathBuf> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let src = fs::read_to_string(path).unwrap_or_default();
+    src.lines()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap_or("");
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("mesh"), Some(file)) => Some(dir.join(file)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// `deg ax ay az [cx cy cz]`
+fn rotation(words: Words) -> Result<(f64, Vec3, Option<Point3>), String> {
+    let [deg, x, y, z] = numbers::<4>(words)?;
+    let axis = Vec3::new(x, y, z);
+    if axis.norm() == 0. {
+        return Err("rotation axis can't be zero".to_owned());
+    }
+    let rest: Vec<_> = words.collect();
+    let center = match rest.len() {
+        0 => None,
+        3 => {
+            let [x, y, z] = numbers::<3>(&mut rest.into_iter())?;
+            Some(Point3::new(x, y, z))
+        },
+        n => return Err(format!("expected 0 or 3 centre coordinates, found {}", n)),
+    };
+    Ok((deg.to_radians(), axis.normalize(), center))
+}
+
+fn parse_scene(path: &Path, src: &str) -> Result<Scene, LoadError> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut scene = Scene::new();
+    let mut object: Option<ObjectDef> = None;
+
+    for (i, line) in src.lines().enumerate() {
+        let line_no = i + 1;
+        let err = |msg: String| LoadError::parse(path, line_no, msg);
+
+        let line = line.split('#').next().unwrap_or("");
+        let mut words = line.split_whitespace();
+        let keyword = match words.next() {
+            Some(k) => k,
+            None => continue,
+        };
+
+        if let Some(def) = object.as_mut() {
+            match keyword {
+                "color" => {
+                    let [r, g, b] = numbers::<3>(&mut words).map_err(err)?;
+                    let channel = |x: f64| x.clamp(0., 255.) as u8;
+                    def.color = Some(Rgb(channel(r), channel(g), channel(b)));
+                },
+                "mesh" => {
+                    let file = words.next()
+                        .ok_or_else(|| err("mesh needs a file name".to_owned()))?;
+                    let file = dir.join(file);
+                    let mesh = match file.extension().and_then(|e| e.to_str()) {
+                        Some(e) if e.eq_ignore_ascii_case("obj") => Object::from_obj(&file)?,
+                        Some(e) if e.eq_ignore_ascii_case("stl") => Object::from_stl(&file)?,
+                        _ => return Err(err(format!(
+                            "unsupported mesh format `{}`", file.display()))),
+                    };
+                    def.meshes.push(mesh);
+                },
+                "triangle" => {
+                    let c = numbers::<9>(&mut words).map_err(err)?;
+                    def.polygons.push(Polygon::Triangle(
+                        Point3::new(c[0], c[1], c[2]),
+                        Point3::new(c[3], c[4], c[5]),
+                        Point3::new(c[6], c[7], c[8]),
+                    ));
+                },
+                "sphere" => {
+                    let [x, y, z, r] = numbers::<4>(&mut words).map_err(err)?;
+                    if r <= 0. {
+                        return Err(err("sphere radius must be positive".to_owned()));
+                    }
+                    def.polygons.push(Polygon::Sphere(
+                        Sphere::new(Point3::new(x, y, z), r)));
+                },
+                "disc" => {
+                    let c = numbers::<7>(&mut words).map_err(err)?;
+                    let normal = Vec3::new(c[3], c[4], c[5]);
+                    if normal.norm() == 0. || c[6] <= 0. {
+                        return Err(err("disc needs a normal and a positive radius".to_owned()));
+                    }
+                    def.polygons.push(Polygon::Disc(
+                        Disc::new(Point3::new(c[0], c[1], c[2]), normal, c[6])));
+                },
+                "plane" => {
+                    let c = numbers::<6>(&mut words).map_err(err)?;
+                    let normal = Vec3::new(c[3], c[4], c[5]);
+                    if normal.norm() == 0. {
+                        return Err(err("plane normal can't be zero".to_owned()));
+                    }
+                    def.polygons.push(Polygon::Plane(
+                        Plane::new(Point3::new(c[0], c[1], c[2]), normal)));
+                },
+                "fit" => {
+                    let c = numbers::<6>(&mut words).map_err(err)?;
+                    def.steps.push(Step::Fit([
+                        Point3::new(c[0], c[1], c[2]),
+                        Point3::new(c[3], c[4], c[5]),
+                    ]));
+                },
+                "scale" => {
+                    let [x, y, z] = numbers::<3>(&mut words).map_err(err)?;
+                    def.steps.push(Step::Transform(
+                        Transform::scale(Vec3::new(x, y, z))));
+                },
+                "translate" => {
+                    let [x, y, z] = numbers::<3>(&mut words).map_err(err)?;
+                    def.steps.push(Step::Transform(
+                        Transform::translate(Vec3::new(x, y, z))));
+                },
+                "rotate" => {
+                    let (t, axis, center) = rotation(&mut words).map_err(err)?;
+                    def.steps.push(Step::Transform(match center {
+                        Some(c) => Transform::pivot(t, axis, c),
+                        None => Transform::rotate(t, axis),
+                    }));
+                },
+                "end" => {
+                    scene.add(build_object(object.take().unwrap()));
+                },
+                k => return Err(err(format!("unknown object statement `{}`", k))),
+            }
+            no_more(&mut words).map_err(err)?;
+            continue;
+        }
+
+        match keyword {
+            "object" => {
+                object = Some(ObjectDef {
+                    line: line_no,
+                    color: None,
+                    meshes: Vec::new(),
+                    polygons: Vec::new(),
+                    steps: Vec::new(),
+                });
+            },
+            "camera" => match words.next() {
+                Some("translate") => {
+                    let [x, y, z] = numbers::<3>(&mut words).map_err(err)?;
+                    scene.camera.apply(&Transform::translate(Vec3::new(x, y, z)));
+                },
+                Some("rotate") => {
+                    let (t, axis, center) = rotation(&mut words).map_err(err)?;
+                    let center = center.unwrap_or_else(|| scene.camera.get_pivot());
+                    scene.camera.apply(&Transform::pivot(t, axis, center));
+                },
+                Some("zoom") => {
+                    let [f] = numbers::<1>(&mut words).map_err(err)?;
+                    if !(f.is_finite() && f > 0.) {
+                        return Err(err("zoom needs a finite factor above 0".to_owned()));
+                    }
+                    scene.camera.zoom(f);
+                },
+                Some("orthographic") => {
+                    scene.camera.set_projection(Projection::Orthographic);
+                },
+                Some(w) => return Err(err(format!("unknown camera statement `{}`", w))),
+                None => return Err(err("camera needs a statement".to_owned())),
+            },
+            "light" => {
+                let mut light = match words.next() {
+                    Some("ambient") => {
+                        let [i] = numbers::<1>(&mut words).map_err(err)?;
+                        Light::ambient(i)
+                    },
+                    Some("directional") => {
+                        let [x, y, z, i] = numbers::<4>(&mut words).map_err(err)?;
+                        let dir = Vec3::new(x, y, z);
+                        if dir.norm() == 0. {
+                            return Err(err("light direction can't be zero".to_owned()));
+                        }
+                        Light::directional(dir, i)
+                    },
+                    Some("point") => {
+                        let [x, y, z, i] = numbers::<4>(&mut words).map_err(err)?;
+                        Light::point(Point3::new(x, y, z), i)
+                    },
+                    Some(w) => return Err(err(format!("unknown light kind `{}`", w))),
+                    None => return Err(err("light needs a kind".to_owned())),
+                };
+                match words.next() {
+                    Some("shadows") => { light = light.with_shadows(true); },
+                    Some(w) => return Err(err(format!("unexpected `{}`", w))),
+                    None => {},
+                }
+                scene.lights.push(light);
+            },
+            "shadow_bias" => {
+                let [b] = numbers::<1>(&mut words).map_err(err)?;
+                scene.shadow_bias = b;
+            },
+            "end" => return Err(err("`end` without `object`".to_owned())),
+            k => return Err(err(format!("unknown statement `{}`", k))),
+        }
+        no_more(&mut words).map_err(err)?;
+    }
+
+    if let Some(def) = object {
+        return Err(LoadError::parse(path, def.line, "object is missing its `end`"));
+    }
+    Ok(scene)
+}
+
+fn build_object(def: ObjectDef) -> Object {
+    let mut polygons = Vec::new();
+    let mut colors = Vec::new();
+    let mut normals = Vec::new();
+    let keep_colors = def.color.is_none()
+        && def.meshes.iter().any(|m| !m.colors.is_empty());
+    let keep_normals = def.meshes.iter().any(|m| !m.normals.is_empty());
+
+    // curved polygons ignore shading normals, any placeholder will do
+    let normal_of = |poly: &Polygon| poly.normal().unwrap_or(Vec3::new(0., 0., 0.));
+
+    let base = def.color.unwrap_or(mesh::DEFAULT_COLOR);
+    for mesh in def.meshes {
+        for (i, poly) in mesh.polygons.iter().enumerate() {
+            colors.push(mesh.color_of(i));
+            normals.push(mesh.normals.get(i).cloned()
+                         .unwrap_or_else(|| normal_of(poly)));
+        }
+        polygons.extend(mesh.polygons);
+    }
+    for poly in def.polygons {
+        colors.push(base);
+        normals.push(normal_of(&poly));
+        polygons.push(poly);
+    }
+
+    let mut obj = Object::new(base, polygons);
+    if keep_colors {
+        obj.colors = colors;
+    }
+    if keep_normals {
+        obj.normals = normals;
+    }
+
+    for step in def.steps {
+        match step {
+            Step::Fit(target) => { obj.fit_to_box(target); },
+            Step::Transform(t) => { obj.apply(&t); },
+        }
+    }
+    obj
+}
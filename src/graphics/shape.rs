@@ -0,0 +1,66 @@
+//! The interface between a `Scene` and the things in it.
+
+use termion::color::Rgb;
+
+use super::*;
+use super::scene::Object;
+
+/// Anything a `Scene` can render.
+///
+/// A shape may be made of several parts, e.g. the polygons of an `Object`,
+/// which its hits report in `Hit::poly` so each can have its own colour.
+/// Shapes are shared between the render threads, hence `Send + Sync`.
+pub trait Shape: Intersectable + Send + Sync {
+    /// Axis-aligned bounds, which may be infinite for unbounded shapes.
+    /// Must be up to date after `transform`.
+    fn bounds(&self) -> [Point3; 2];
+
+    /// Moves the shape, like `Transformable::apply`.
+    fn transform(&mut self, t: &Transform);
+
+    /// Colour of the part at index `part`.
+    fn color_of(&self, part: usize) -> Rgb;
+
+    /// Normal used for lighting at `hit`, facing the same way as
+    /// `hit.normal`. The geometric normal unless overridden, e.g. to
+    /// smooth out flat polygons.
+    fn normal_at(&self, hit: &Hit) -> Vec3 {
+        hit.normal
+    }
+
+    /// Whether the shape lies on `ray` closer than `max_dist`, for shadow
+    /// rays. Worth overriding when there is a cheaper test than finding
+    /// the closest hit.
+    fn occludes(&self, ray: &Ray3, max_dist: f64) -> bool {
+        self.intersect(ray).is_some_and(|hit| hit.dist < max_dist)
+    }
+}
+
+impl Shape for Object {
+    fn bounds(&self) -> [Point3; 2] {
+        self.bounds
+    }
+
+    fn transform(&mut self, t: &Transform) {
+        self.apply(t);
+    }
+
+    fn color_of(&self, part: usize) -> Rgb {
+        Object::color_of(self, part)
+    }
+
+    fn normal_at(&self, hit: &Hit) -> Vec3 {
+        Object::normal_at(self, hit)
+    }
+
+    fn occludes(&self, ray: &Ray3, max_dist: f64) -> bool {
+        Object::occludes(self, ray, max_dist)
+    }
+}
+
+impl Transformable for Box<dyn Shape> {
+    fn apply(&mut self, t: &Transform) -> &mut Self {
+        self.transform(t);
+        self
+    }
+}
@@ -0,0 +1,90 @@
+//! Spheres, and the ellipsoids they turn into under non-uniform scaling.
+
+use std::f64::consts::PI;
+
+use super::*;
+
+/// The unit sphere at the origin, placed in the world by a transform.
+///
+/// Rays are intersected in the sphere's own space through the inverse of
+/// that transform, so any affine transform works, not only the ones that
+/// keep it round.
+#[derive(Debug, Clone, Copy)]
+pub struct Sphere {
+    to_world: Transform,
+    /// `None` while `to_world` flattens it, such a sphere is never hit.
+    to_local: Option<Transform>,
+}
+
+impl Sphere {
+    pub fn new(center: Point3, radius: f64) -> Self {
+        let mut to_world = Transform::scale(Vec3::new(radius, radius, radius));
+        to_world.apply(&Transform::translate(center.into()));
+        Sphere {
+            to_world,
+            to_local: to_world.inverse(),
+        }
+    }
+
+    pub fn center(&self) -> Point3 {
+        let m = &self.to_world.0;
+        Point3::new(m[0][3], m[1][3], m[2][3])
+    }
+
+    /// Tight axis-aligned bounds of the (possibly stretched) sphere.
+    pub fn bounds(&self) -> [Point3; 2] {
+        let m = &self.to_world.0;
+        let half = |i: usize| {
+            (m[i][0] * m[i][0] + m[i][1] * m[i][1] + m[i][2] * m[i][2]).sqrt()
+        };
+        let half = Point3::new(half(0), half(1), half(2));
+        let c = self.center();
+        [c - half, c + half]
+    }
+}
+
+impl Transformable for Sphere {
+    fn apply(&mut self, t: &Transform) -> &mut Self {
+        self.to_world.apply(t);
+        self.to_local = self.to_world.inverse();
+        self
+    }
+}
+
+impl Intersectable for Sphere {
+    fn intersect(&self, ray: &Ray3) -> Option<Hit> {
+        const EPSILON: f64 = 0.0000001;
+
+        let to_local = self.to_local.as_ref()?;
+        let mut o = ray.origin;
+        let mut v = ray.vec;
+        o.apply(to_local);
+        v.apply(to_local);
+        let o = Vec3::from(o);
+
+        // |o + v*t| = 1, affine maps keep t the same in both spaces
+        let a = v.dot(&v);
+        let b = o.dot(&v);
+        let c = o.dot(&o) - 1.;
+        let disc = b * b - a * c;
+        if disc < 0. {
+            return None;
+        }
+        let sq = disc.sqrt();
+        let t = [(-b - sq) / a, (-b + sq) / a].iter().cloned()
+            .find(|&t| t > EPSILON)?;
+
+        let p = ray.origin + Point3::from(ray.vec * t);
+        // the local normal is the local point, taken to the world by the
+        // transpose of the inverse
+        let local = o + v * t;
+        let (x, y, z) = local.unpack();
+        let uv = (
+            (z.atan2(x) / (2. * PI)).rem_euclid(1.),
+            y.clamp(-1., 1.).asin() / PI + 0.5,
+        );
+        let mut n = local;
+        n.apply(&to_local.transpose_linear());
+        Some(Hit::new(ray, p, n.normalize(), uv))
+    }
+}
@@ -0,0 +1,283 @@
+//! What the keys do: `Action`s on the camera and the `Keymap` binding keys
+//! to them, which can be loaded from a text file.
+//!
+//! ```text
+//! # comments run to the end of the line, angles are in degrees
+//! clear                       # forget the default bindings
+//! q        quit
+//! ctrl-c   quit
+//! comma    move-forward 2.5   # negative amounts go the other way
+//! o        move-forward -2.5
+//! up       orbit-up 2
+//! right    orbit-right        # amounts default to the built-in steps
+//! r        dolly 5            # towards the pivot
+//! space    eye-distance 5
+//! z        zoom 1.5           # a factor, below 1 zooms out
+//! tab      toggle-projection
+//! f        none               # unbinds the key
+//! ```
+//!
+//! Keys are single characters, `ctrl-` or `alt-` and a character, `f1` to
+//! `f12`, or one of `space`, `tab`, `enter`, `esc`, `backspace`, `delete`,
+//! `insert`, `up`, `down`, `left`, `right`, `home`, `end`, `pageup`,
+//! `pagedown`, `hash` and `comma`. Later lines override earlier ones.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fs;
+use std::path::Path;
+
+use termion::event::Key;
+
+use graphics::*;
+use graphics::camera::{Camera, Projection};
+use graphics::mesh::LoadError;
+
+/// Something the user can ask for, with its step size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Quit,
+    /// Moves the camera along its view direction, backwards when negative.
+    MoveForward(f64),
+    /// Moves the camera sideways, to the left when negative.
+    MoveRight(f64),
+    /// Moves the camera up, down when negative.
+    MoveUp(f64),
+    /// Orbits the camera around `Camera::get_pivot` by an angle in radians,
+    /// to the left when negative.
+    OrbitRight(f64),
+    /// Orbits the camera around `Camera::get_pivot` by an angle in radians,
+    /// downwards when negative.
+    OrbitUp(f64),
+    /// Moves the camera towards `Camera::get_pivot` along its view, away
+    /// from it when negative, see `Camera::dolly`.
+    Dolly(f64),
+    /// Moves the eye back from the screen, which narrows the field of view
+    /// without moving the screen. Negative amounts move it closer and widen
+    /// the view.
+    EyeDistance(f64),
+    /// Makes things look bigger by a factor above 0, see `Camera::zoom`.
+    Zoom(f64),
+    /// Switches between perspective and orthographic projection.
+    ToggleProjection,
+}
+
+/// Keys bound to `Action`s.
+#[derive(Debug, Clone)]
+pub struct Keymap(HashMap<Key, Action>);
+
+const MOVE_STEP: f64 = 2.5;
+const ORBIT_STEP: f64 = PI*2./(60.*3.);
+const EYE_STEP: f64 = 5.;
+const ZOOM_STEP: f64 = 1.25;
+
+impl Action {
+    /// Carries out the action on `camera`, `Quit` is left to the caller.
+    pub fn apply_to(&self, camera: &mut Camera) {
+        match *self {
+            Action::Quit => {},
+            Action::MoveForward(d) => {
+                camera.apply_rel(Transform::translate(Vec3::new(0., 0., -d)));
+            },
+            Action::MoveRight(d) => {
+                camera.apply_rel(Transform::translate(Vec3::new(d, 0., 0.)));
+            },
+            Action::MoveUp(d) => {
+                // rows of the screen go down
+                camera.apply_rel(Transform::translate(Vec3::new(0., -d, 0.)));
+            },
+            Action::OrbitRight(a) => {
+                let pivot = camera.get_pivot();
+                camera.apply(&Transform::pivot(a, Vec3::new(0., -1., 0.), pivot));
+            },
+            Action::OrbitUp(a) => {
+                let pivot = camera.get_pivot();
+                camera.apply(&Transform::pivot(a, Vec3::new(-1., 0., 0.), pivot));
+            },
+            Action::Dolly(d) => {
+                camera.dolly(d);
+            },
+            Action::EyeDistance(d) => {
+                camera.eye.as_rel_mut()
+                    .apply(&Transform::translate(Vec3::new(0., 0., d)));
+            },
+            Action::Zoom(f) => {
+                camera.zoom(f);
+            },
+            Action::ToggleProjection => {
+                let p = match camera.get_projection() {
+                    Projection::Perspective => Projection::Orthographic,
+                    Projection::Orthographic => Projection::Perspective,
+                };
+                camera.set_projection(p);
+            },
+        }
+    }
+
+    /// The action called `name` in keymap files, `amount` being in degrees
+    /// for orbits.
+    fn parse(name: &str, amount: Option<f64>) -> Result<Action, String> {
+        let step = |default: f64| amount.unwrap_or(default);
+        let action = match name {
+            "quit" => Action::Quit,
+            "move-forward" => Action::MoveForward(step(MOVE_STEP)),
+            "move-right" => Action::MoveRight(step(MOVE_STEP)),
+            "move-up" => Action::MoveUp(step(MOVE_STEP)),
+            "orbit-right" =>
+                Action::OrbitRight(amount.map_or(ORBIT_STEP, f64::to_radians)),
+            "orbit-up" =>
+                Action::OrbitUp(amount.map_or(ORBIT_STEP, f64::to_radians)),
+            "dolly" => Action::Dolly(step(MOVE_STEP)),
+            "eye-distance" => Action::EyeDistance(step(EYE_STEP)),
+            "zoom" => match step(ZOOM_STEP) {
+                f if f.is_finite() && f > 0. => Action::Zoom(f),
+                _ => return Err("zoom needs a finite factor above 0".to_owned()),
+            },
+            "toggle-projection" => Action::ToggleProjection,
+            _ => return Err(format!("unknown action `{}`", name)),
+        };
+        match action {
+            Action::Quit | Action::ToggleProjection if amount.is_some() =>
+                Err(format!("{} takes no amount", name)),
+            _ => Ok(action),
+        }
+    }
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let single = |s: &str| {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(c) = single(name) {
+        return Some(Key::Char(c));
+    }
+    if let Some(c) = name.strip_prefix("ctrl-").and_then(single) {
+        return Some(Key::Ctrl(c));
+    }
+    if let Some(c) = name.strip_prefix("alt-").and_then(single) {
+        return Some(Key::Alt(c));
+    }
+    if let Some(n) = name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+        return if (1..=12).contains(&n) { Some(Key::F(n)) } else { None };
+    }
+    Some(match name {
+        "space" => Key::Char(' '),
+        "tab" => Key::Char('\t'),
+        "enter" => Key::Char('\n'),
+        "hash" => Key::Char('#'),
+        "comma" => Key::Char(','),
+        "esc" => Key::Esc,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "insert" => Key::Insert,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        _ => return None,
+    })
+}
+
+impl Keymap {
+    /// A keymap with nothing bound, see `Default` for the built-in one.
+    pub fn new() -> Self {
+        Keymap(HashMap::new())
+    }
+
+    /// Binds `key` to `action`, replacing what it was bound to.
+    pub fn bind(&mut self, key: Key, action: Action) -> &mut Self {
+        self.0.insert(key, action);
+        self
+    }
+
+    pub fn unbind(&mut self, key: Key) -> &mut Self {
+        self.0.remove(&key);
+        self
+    }
+
+    /// The action bound to `key`, if any.
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.0.get(&key).cloned()
+    }
+
+    /// Reads a keymap file over the default bindings, see the module docs
+    /// for the format.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Keymap, LoadError> {
+        let path = path.as_ref();
+        let src = fs::read_to_string(path)
+            .map_err(|e| LoadError::io(path, e))?;
+        let mut keymap = Keymap::default();
+        for (i, line) in src.lines().enumerate() {
+            keymap.parse_line(line)
+                .map_err(|msg| LoadError::parse(path, i + 1, msg))?;
+        }
+        Ok(keymap)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let line = line.split('#').next().unwrap_or("");
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [] => Ok(()),
+            ["clear"] => {
+                self.0.clear();
+                Ok(())
+            },
+            [key, "none"] => {
+                let key = parse_key(key)
+                    .ok_or_else(|| format!("unknown key `{}`", key))?;
+                self.unbind(key);
+                Ok(())
+            },
+            [key, name] | [key, name, _] => {
+                let key = parse_key(key)
+                    .ok_or_else(|| format!("unknown key `{}`", key))?;
+                let amount = match words.get(2) {
+                    Some(w) => Some(w.parse().map_err(|_| {
+                        format!("expected a number, found `{}`", w)
+                    })?),
+                    None => None,
+                };
+                self.bind(key, Action::parse(name, amount)?);
+                Ok(())
+            },
+            _ => Err("expected a key, an action and maybe an amount".to_owned()),
+        }
+    }
+}
+
+impl Default for Keymap {
+    /// `wasd` to move, shifted to orbit, `r` and `R` to dolly towards the
+    /// pivot and back, `f` and `F` to move the eye away from the screen and
+    /// back, `+` and `-` to zoom, `p` to switch projections and `q` to quit.
+    fn default() -> Self {
+        let mut keymap = Keymap::new();
+        keymap
+            .bind(Key::Char('q'), Action::Quit)
+            .bind(Key::Char('w'), Action::MoveForward(MOVE_STEP))
+            .bind(Key::Char('s'), Action::MoveForward(-MOVE_STEP))
+            .bind(Key::Char('a'), Action::MoveRight(-MOVE_STEP))
+            .bind(Key::Char('d'), Action::MoveRight(MOVE_STEP))
+            .bind(Key::Char('W'), Action::OrbitUp(ORBIT_STEP))
+            .bind(Key::Char('S'), Action::OrbitUp(-ORBIT_STEP))
+            .bind(Key::Char('A'), Action::OrbitRight(-ORBIT_STEP))
+            .bind(Key::Char('D'), Action::OrbitRight(ORBIT_STEP))
+            .bind(Key::Char('r'), Action::Dolly(MOVE_STEP))
+            .bind(Key::Char('R'), Action::Dolly(-MOVE_STEP))
+            .bind(Key::Char('f'), Action::EyeDistance(EYE_STEP))
+            .bind(Key::Char('F'), Action::EyeDistance(-EYE_STEP))
+            .bind(Key::Char('+'), Action::Zoom(ZOOM_STEP))
+            .bind(Key::Char('='), Action::Zoom(ZOOM_STEP))
+            .bind(Key::Char('-'), Action::Zoom(1. / ZOOM_STEP))
+            .bind(Key::Char('p'), Action::ToggleProjection);
+        keymap
+    }
+}
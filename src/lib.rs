@@ -0,0 +1,31 @@
+//! ascworld: a tiny ray-casting renderer that draws scenes into the terminal.
+//!
+//! The `graphics` module holds everything a downstream crate needs: the
+//! linear algebra primitives (`Point3`, `Vec3`, `Transform`, `Ray3`), the
+//! `camera` and the `scene` along with its `RenderedScene` output. `input`
+//! maps keys to what they do to the camera.
+
+extern crate ndarray;
+
+extern crate termion;
+
+#[macro_use]
+mod macros;
+
+pub mod graphics;
+pub mod input;
+
+pub use graphics::*;
+pub use graphics::camera::{Camera, Perspective, Projection, Screen};
+pub use graphics::display::{ColorMode, Display, Glyph, Ramp, Style};
+pub use graphics::light::{Light, LightKind};
+pub use graphics::mesh::LoadError;
+pub use graphics::plane::{Disc, Plane};
+pub use graphics::scene::{Cell, Object, Pick, RenderedScene, Scene};
+pub use graphics::shape::Shape;
+pub use graphics::sphere::Sphere;
+pub use input::{Action, Keymap};
+
+/// Colour type used by `Object` and `Cell`, re-exported so downstream crates
+/// don't have to pin their own `termion`.
+pub use termion::color::Rgb;
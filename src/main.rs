@@ -1,33 +1,31 @@
-#[macro_use]
-extern crate ndarray;
-
+extern crate ascworld;
 extern crate termion;
 
-#[macro_use]
-mod macros;
-
-mod graphics;
-
 use termion::color::*;
 use termion::{
     cursor,
     clear,
     event,
     raw::IntoRawMode,
-    input::TermRead,
+    input::{MouseTerminal, TermRead},
+    terminal_size,
 };
 
-use graphics::*;
-use graphics::camera::*;
-use graphics::scene::*;
+use ascworld::*;
 
-use std::io::{Write, Read};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process;
 
 use std::time::{
     Instant,
     Duration,
+    SystemTime,
 };
 
+use std::f64::consts::PI;
 use std::thread;
 use std::sync::mpsc::channel;
 use std::sync::{
@@ -35,61 +33,320 @@ use std::sync::{
     RwLock,
 };
 
-fn main() {
-    let mut scene = Scene::new();
+const USAGE: &str = "\
+usage: ascworld-01 [--watch] [--color MODE] [--style STYLE] [--ramp CHARS]
+                   [--keymap FILE] [SCENE_FILE]
+       ascworld-01 --frames N [--output FILE] [--size WxH] [SCENE_FILE]
+
+MODE is one of truecolor, 256, 16 or mono, guessed from
+COLORTERM and TERM when not given.
+STYLE is one of blocks, ascii, ascii-color or braille, --ramp picks the
+characters of the ascii styles from darkest to brightest.
+--frames renders N frames of the animation without a terminal, or a
+single frame of SCENE_FILE, FILE (frame.png by default, .png or .ppm) is
+numbered as frame-0000.png, frame-0001.png and so on, WxH is the image
+size (256x256 by default).
+--keymap rebinds keys, by default from ascworld/keymap in XDG_CONFIG_HOME
+or ~/.config when it exists.";
+
+struct Args {
+    scene: Option<PathBuf>,
+    watch: bool,
+    color: Option<ColorMode>,
+    style: Style,
+    frames: Option<usize>,
+    output: PathBuf,
+    size: (usize, usize),
+    keymap: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        scene: None,
+        watch: false,
+        color: None,
+        style: Style::HalfBlock,
+        frames: None,
+        output: PathBuf::from("frame.png"),
+        size: (256, 256),
+        keymap: None,
+    };
+    let mut ramp = None;
+    let mut argv = env::args().skip(1);
+    while let Some(arg) = argv.next() {
+        match arg.as_str() {
+            "-w" | "--watch" => args.watch = true,
+            "-c" | "--color" => {
+                let mode = argv.next()
+                    .ok_or_else(|| format!("{} needs a mode\n{}", arg, USAGE))?;
+                args.color = Some(mode.parse()?);
+            },
+            "-s" | "--style" => {
+                let style = argv.next()
+                    .ok_or_else(|| format!("{} needs a style\n{}", arg, USAGE))?;
+                args.style = style.parse()?;
+            },
+            "-r" | "--ramp" => {
+                let chars = argv.next()
+                    .ok_or_else(|| format!("{} needs characters\n{}", arg, USAGE))?;
+                ramp = Some(Ramp::new(&chars)?);
+            },
+            "-n" | "--frames" => {
+                let n = argv.next()
+                    .ok_or_else(|| format!("{} needs a count\n{}", arg, USAGE))?;
+                args.frames = Some(n.parse()
+                    .map_err(|_| format!("invalid frame count `{}`", n))?);
+            },
+            "-o" | "--output" => {
+                let file = argv.next()
+                    .ok_or_else(|| format!("{} needs a file\n{}", arg, USAGE))?;
+                args.output = PathBuf::from(file);
+            },
+            "--size" => {
+                let size = argv.next()
+                    .ok_or_else(|| format!("{} needs a size\n{}", arg, USAGE))?;
+                let invalid = || format!("invalid size `{}`, expected WxH", size);
+                let (w, h) = size.split_once('x').ok_or_else(invalid)?;
+                args.size = (
+                    w.parse().map_err(|_| invalid())?,
+                    h.parse().map_err(|_| invalid())?,
+                );
+                if args.size.0 == 0 || args.size.1 == 0 {
+                    return Err(invalid());
+                }
+            },
+            "-k" | "--keymap" => {
+                let file = argv.next()
+                    .ok_or_else(|| format!("{} needs a file\n{}", arg, USAGE))?;
+                args.keymap = Some(PathBuf::from(file));
+            },
+            "-h" | "--help" => return Err(USAGE.to_owned()),
+            a if a.starts_with('-') =>
+                return Err(format!("unknown option `{}`\n{}", a, USAGE)),
+            _ if args.scene.is_some() =>
+                return Err(format!("more than one scene file\n{}", USAGE)),
+            _ => args.scene = Some(PathBuf::from(arg)),
+        }
+    }
+    if args.watch && args.scene.is_none() {
+        return Err(format!("--watch needs a scene file\n{}", USAGE));
+    }
+    // only the built-in demo is animated, more frames of a scene file
+    // would all be the same
+    if args.scene.is_some() && args.frames.is_some_and(|n| n > 1) {
+        return Err(format!("--frames above 1 needs the built-in demo, \
+            scene files aren't animated\n{}", USAGE));
+    }
+    if let Some(r) = ramp {
+        args.style = match args.style {
+            Style::Ramp{color, ..} => Style::Ramp { ramp: r, color },
+            // a ramp alone asks for ascii
+            _ => Style::Ramp { ramp: r, color: false },
+        };
+    }
+    Ok(args)
+}
+
+/// The keymap file to use when `--keymap` isn't given, if there is one.
+fn default_keymap() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(config.join("ascworld").join("keymap")).filter(|p| p.is_file())
+}
+
+/// Polls the modification times of a scene file and of the meshes it
+/// refers to, see `scene_file::mesh_files`.
+struct Watch {
+    path: PathBuf,
+    modified: Vec<(PathBuf, Option<SystemTime>)>,
+    checked: Instant,
+}
+
+impl Watch {
+    const INTERVAL: Duration = Duration::from_millis(250);
+
+    fn new(path: PathBuf) -> Self {
+        Watch {
+            modified: Self::mtimes(&path),
+            checked: Instant::now(),
+            path,
+        }
+    }
+
+    /// The files to watch, looked up afresh as the scene file may have
+    /// changed which meshes it uses.
+    fn mtimes(path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let mtime = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+        Some(path.to_owned()).into_iter()
+            .chain(scene_file::mesh_files(path))
+            .map(|p| { let t = mtime(&p); (p, t) })
+            .collect()
+    }
+
+    fn changed(&mut self) -> bool {
+        if self.checked.elapsed() < Self::INTERVAL {
+            return false;
+        }
+        self.checked = Instant::now();
+
+        let modified = Self::mtimes(&self.path);
+        if modified != self.modified {
+            self.modified = modified;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Fits the camera to the terminal below the status line, returns whether
+/// anything changed.
+fn fit_terminal(camera: &mut Camera, style: &Style) -> bool {
+    let (cols, rows) = terminal_size().unwrap_or((64, 33));
+    let (w, h) = style.cells_per_glyph();
+    let size = (cols as usize * w, (rows as usize).saturating_sub(1) * h);
+
+    let (cw, ch) = *camera.get_cell_size();
+    if size == *camera.get_screen_size() && cw / ch == style.cell_aspect() {
+        return false;
+    }
+    camera.resize(size, style.cell_aspect());
+    true
+}
+
+/// Orbits the camera around its pivot by `dx` and `dy` terminal cells of
+/// mouse movement.
+fn orbit(camera: &mut Camera, dx: f64, dy: f64) {
+    const PER_CELL: f64 = PI/90.;
+    Action::OrbitRight(dx * PER_CELL).apply_to(camera);
+    Action::OrbitUp(-dy * PER_CELL).apply_to(camera);
+}
+
+/// `frame.png` becomes `frame-0007.png` for frame 7.
+fn numbered(path: &Path, frame: usize) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    let mut name = format!("{}-{:04}", stem, frame);
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        name = name + "." + ext;
+    }
+    path.with_file_name(name)
+}
+
+/// Renders `frames` frames into numbered image files, no terminal needed.
+fn render_frames(mut scene: Scene, args: &Args, frames: usize, animate: bool)
+    -> ::std::io::Result<()> {
+
+    scene.camera.resize(args.size, 1.);
     let mut rendered_s = scene.empty_render();
+    for frame in 0..frames {
+        scene.render(&mut rendered_s);
+        let path = numbered(&args.output, frame);
+        rendered_s.save(&path)?;
+        println!("{}", path.display());
+        if animate {
+            step(&mut scene);
+        }
+    }
+    Ok(())
+}
 
-    scene.objects.push(
-        Object::new(
+/// Advances the built-in demo by one frame.
+fn step(scene: &mut Scene) {
+    // spin the cube, the floor stays put
+    scene.objects_mut().iter_mut().take(1).for_each(|o| {
+        o.apply(
+            &Transform::pivot(
+                PI*2./(60.*4.),
+                Vec3::new(0., 1., 0.),
+                Point3::new(15.,15.,-15.)
+            )
+        );
+    });
+}
+
+/// Spinning cube over a floor, used when no scene file is given.
+fn demo_scene() -> Scene {
+    let mut scene = Scene::new();
+
+    scene.add(
+        Object::cube(
             Rgb(55, 155, 255),
-            vec![
-                Polygon::Triangle(
-                    Point3::new(10., 10., -10.),
-                    Point3::new(10., 20., -10.),
-                    Point3::new(20., 20., -10.),
-                ),
-                Polygon::Triangle(
-                    Point3::new(10., 10., -10.),
-                    Point3::new(20., 10., -10.),
-                    Point3::new(20., 20., -10.),
-                ),
-            ],
+            Point3::new(10., 10., -20.),
+            Point3::new(20., 20., -10.),
+        )
+    );
+
+    // floor under the cube to catch its shadow
+    scene.add(
+        Object::new(
+            Rgb(200, 200, 200),
+            vec![Polygon::Plane(
+                Plane::new(Point3::new(0., 24., 0.), Vec3::new(0., -1., 0.))
+            )],
         )
     );
-   scene.objects.push(
-       Object::new(
-           Rgb(155, 55, 155),
-           vec![
-               Polygon::Triangle(
-                   Point3::new(10., 10., -10.),
-                   Point3::new(10., 20., -10.),
-                   Point3::new(10., 20., -20.),
-               ),
-               Polygon::Triangle(
-                   Point3::new(10., 10., -10.),
-                   Point3::new(10., 10., -20.),
-                   Point3::new(10., 20., -20.),
-               ),
-           ],
-       )
-   );
-   scene.objects.push(
-       Object::new(
-           Rgb(155, 255, 55),
-           vec![
-               Polygon::Triangle(
-                   Point3::new(10., 10., -20.),
-                   Point3::new(20., 10., -20.),
-                   Point3::new(20., 20., -10.),
-               ),
-           ],
-       )
-   );
+
+    scene.lights.push(Light::ambient(0.2));
+    scene.lights.push(
+        Light::directional(Vec3::new(-0.4, 0.6, -1.), 0.8)
+            .with_shadows(true)
+    );
+
+    scene
+}
+
+fn main() {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+
+    let mut scene = match args.scene {
+        Some(ref path) => Scene::from_file(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => demo_scene(),
+    };
+
+    let keymap = match args.keymap.clone().or_else(default_keymap) {
+        Some(path) => Keymap::from_file(path).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        None => Keymap::default(),
+    };
+
+    // only the built-in demo is animated
+    let animate = args.scene.is_none();
+    if let Some(frames) = args.frames {
+        render_frames(scene, &args, frames, animate).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        });
+        return;
+    }
+    let mut rendered_s = scene.empty_render();
+    let mut watch = match args.scene {
+        Some(path) if args.watch => Some(Watch::new(path)),
+        _ => None,
+    };
+    let mut status: Option<String> = None;
+    let mut selected: Option<Pick> = None;
+    // last mouse position while the left button is down,
+    // and whether it moved since the press
+    let mut drag: Option<((u16, u16), bool)> = None;
+    // the status line takes the first terminal row
+    let mut display = Display::new((1, 2));
+    if let Some(mode) = args.color {
+        display.set_color_mode(mode);
+    }
+    display.set_style(args.style);
 
     let mut s = Instant::now();
     let mut e = s.elapsed();
-    let mut stdout = ::std::io::stdout().into_raw_mode().unwrap();
-    let mut running = Arc::new(RwLock::new(true));
+    let mut stdout = MouseTerminal::from(::std::io::stdout().into_raw_mode().unwrap());
+    let running = Arc::new(RwLock::new(true));
     let events = ::std::io::stdin().events();
 
     let (te, re) = channel();
@@ -105,103 +362,120 @@ fn main() {
     });
 
     while *running.read().unwrap() {
-        write!(stdout, "{}{}{}{}\n\r",
+        write!(stdout, "{}{}{} {}B{}{} {}{}{}\n\r",
               cursor::Goto(1,1),
               Fg(Rgb(200,200,55)),
               (1000./(e.as_secs() as f64 *1000.
                     + e.subsec_millis() as f64)).to_string() + "fps",
+              display.bytes_written(),
+              match scene.camera.get_projection() {
+                  Projection::Perspective => "",
+                  Projection::Orthographic => " ortho",
+              },
+              selected.map_or(String::new(), |p| {
+                  let (x, y, z) = p.point.unpack();
+                  format!(" #{} polygon {} at {:.1} {:.1} {:.1}",
+                          p.object, p.poly, x, y, z)
+              }),
+              Fg(Rgb(255,85,85)),
+              status.as_ref().map_or("", |s| s.as_str()),
               clear::UntilNewline,
-        );
+        ).unwrap();
+
+        if let Some(w) = watch.as_mut() {
+            if w.changed() {
+                match Scene::from_file(&w.path) {
+                    Ok(new) => {
+                        scene = new;
+                        rendered_s = scene.empty_render();
+                        status = None;
+                        selected = None;
+                    },
+                    // keep showing the last good scene
+                    Err(e) => status = Some(e.to_string()),
+                }
+            }
+        }
+
+        // also picks up freshly (re)loaded scenes, whose camera
+        // doesn't know about the terminal yet
+        if fit_terminal(&mut scene.camera, display.style()) {
+            rendered_s = scene.empty_render();
+            write!(stdout, "{}", clear::AfterCursor).unwrap();
+            display.invalidate();
+        }
 
         s = Instant::now();
 
         scene.render(&mut rendered_s);
-        rendered_s.display(&mut stdout);
-
-        scene.objects.iter_mut().for_each(|o| {
-            o
-            .apply(
-                &Transform::pivot(
-                    3.1415926*2./(60.*4.),
-                    Vec3::new(0., 1., 0.),
-                    Point3::new(15.,15.,-10.)
-                )
-            )
-//            .apply(
-//                &Transform::pivot(
-//                    3.1415926*2./(60.*4.),
-//                    Vec3::new(0., 0., 1.),
-//                    Point3::new(10.,10.,-10.)
-//                )
-//            )
-            ;
-        });
+        if let Some(p) = selected {
+            rendered_s.highlight(p.object, Rgb(255, 255, 85));
+        }
+        display.draw(&rendered_s, &mut stdout).unwrap();
+
+        if animate {
+            step(&mut scene);
+        }
 
         for event in re.try_iter() {
             match event.unwrap() {
-                event::Event::Key(event::Key::Char('q')) => {
-                    *running.write().unwrap() = false;
-                },
-
-                event::Event::Key(k) => {
-                    match k {
-                          event::Key::Char(c@'w')
-                        | event::Key::Char(c@'a')
-                        | event::Key::Char(c@'s')
-                        | event::Key::Char(c@'d')=> {
-                            scene.camera.apply_rel(
-                                Transform::translate(
-                                    match c {
-                                        'w' => Vec3::new(0., 0., -2.5),
-                                        'a' => Vec3::new(-2.5, 0., 0.),
-                                        's' => Vec3::new(0., 0., 2.5),
-                                        'd' => Vec3::new(2.5, 0., 0.),
-                                        _ => unreachable!(),
-                                    }
-                                )
-                            );
-                        },
-
-                          event::Key::Char(c@'W')
-                        | event::Key::Char(c@'A')
-                        | event::Key::Char(c@'S')
-                        | event::Key::Char(c@'D') => {
-                            let pivot = scene.camera.get_pivot();
-                            scene.camera.apply(
-                                &Transform::pivot(
-                                    3.1415926*2./(60.*3.),
-                                    match c {
-                                        'W' => Vec3::new(-1., 0., 0.),
-                                        'A' => Vec3::new(0., 1., 0.),
-                                        'S' => Vec3::new(1., 0., 0.),
-                                        'D' => Vec3::new(0., -1., 0.),
-                                        _ => unreachable!(),
-                                    },
-                                    pivot
-                                )
+                event::Event::Mouse(m) => match m {
+                    event::MouseEvent::Press(event::MouseButton::Left, x, y) => {
+                        drag = Some(((x, y), false));
+                    },
+                    event::MouseEvent::Hold(x, y) => {
+                        if let Some(((px, py), _)) = drag {
+                            orbit(
+                                &mut scene.camera,
+                                x as f64 - px as f64,
+                                y as f64 - py as f64,
                             );
-                        },
+                            drag = Some(((x, y), true));
+                        }
+                    },
+                    event::MouseEvent::Release(x, y) => {
+                        // a click that didn't drag selects what's under it,
+                        // or clears the selection over the background
+                        if let Some((_, false)) = drag {
+                            selected = display.glyph_at((x, y)).and_then(|g| {
+                                rendered_s.pick_glyph(display.style(), g)
+                            });
+                        }
+                        drag = None;
+                    },
+                    // the wheel dollies the camera along its view, which
+                    // doesn't change sizes without perspective so zoom then
+                    event::MouseEvent::Press(event::MouseButton::WheelUp, ..) => {
+                        match scene.camera.get_projection() {
+                            Projection::Perspective => Action::Dolly(2.5),
+                            Projection::Orthographic => Action::Zoom(1.25),
+                        }.apply_to(&mut scene.camera);
+                    },
+                    event::MouseEvent::Press(event::MouseButton::WheelDown, ..) => {
+                        match scene.camera.get_projection() {
+                            Projection::Perspective => Action::Dolly(-2.5),
+                            Projection::Orthographic => Action::Zoom(0.8),
+                        }.apply_to(&mut scene.camera);
+                    },
+                    _ => {},
+                },
 
-                        event::Key::Char('f') => {
-                            scene.camera.eye.as_rel_mut().apply(
-                                &Transform::translate(Vec3::new(0., 0., 5.))
-                            );
-                        },
-                        event::Key::Char('F') => {
-                            scene.camera.eye.as_rel_mut().apply(
-                                &Transform::translate(Vec3::new(0., 0., -5.))
-                            );
-                        },
-                        _ => {},
-                    }
-                }
+                event::Event::Key(k) => match keymap.action(k) {
+                    Some(Action::Quit) => *running.write().unwrap() = false,
+                    Some(action) => action.apply_to(&mut scene.camera),
+                    // a way out even when a keymap unbinds quit
+                    None if k == event::Key::Ctrl('c') => {
+                        *running.write().unwrap() = false;
+                    },
+                    None => {},
+                },
                 _ => {},
             }
         }
 
         // scene.camera.apply(
         //     &Transform::pivot(
-        //         3.1415926*2./(60.*4.),
+        //         PI*2./(60.*4.),
         //         Vec3::new(1., 0., 0.),
         //         Point3::new(10.,10.,-10.)
         //     )
@@ -214,41 +488,5 @@ fn main() {
         // break;
     }
 
-    event_loop.join();
-
-    return;
-
-    let cell_dims = (0.25, 0.5);
-    // let scrn_dims = (90, 35);
-    let scrn_dims = (2, 2);
-    let mut cam = Camera::new(
-        cell_dims,
-        scrn_dims,
-        Point3::new(
-            cell_dims.0 * scrn_dims.0 as f64 / 2.,
-            cell_dims.1 * scrn_dims.1 as f64 / 2.,
-            45.
-        ),
-    );
-
-    println!("Hello, world!");
-
-    // println!("corners: {:?}", cam.screen.corners);
-    println!("center(1,1): {:?}", cam.get_center((1,1)));
-    println!("ray(1,1): {:?}", cam.get_ray((1,1)));
-    println!("------");
-
-    cam.apply(&Transform::id());
-    cam.apply(&Transform(array![
-                        [1., 0., 0., 0.],
-                        [0., 1., 0., 0.],
-                        [0., 0., 1., 10.],
-                        [0., 0., 0., 1.],
-                        ]));
-
-    // println!("corners: {:?}", cam.screen.corners);
-    println!("center(1,1): {:?}", cam.get_center((1,1)));
-    println!("ray(1,1): {:?}", cam.get_ray((1,1)));
-    // println!("curr_t:  {:?}", cam.current_transform);
-
+    event_loop.join().unwrap();
 }
@@ -0,0 +1,119 @@
+//! The bounding volume hierarchies of objects and scenes against testing
+//! every item, on scattered triangles and random rays.
+
+extern crate ascworld;
+
+use ascworld::*;
+use ascworld::graphics::bvh::Bvh;
+
+/// Small xorshift generator, so the tests see the same scenes every run.
+struct Rng(u64);
+
+impl Rng {
+    /// Uniform in `lo..hi`.
+    fn range(&mut self, lo: f64, hi: f64) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        lo + (hi - lo) * (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn point(&mut self, lo: f64, hi: f64) -> Point3 {
+        Point3::new(self.range(lo, hi), self.range(lo, hi), self.range(lo, hi))
+    }
+}
+
+/// Small triangles scattered through a 40 unit box, and a plane below
+/// them so there is an unbounded item too.
+fn polygons(rng: &mut Rng) -> Vec<Polygon> {
+    let mut polys: Vec<_> = (0..300).map(|_| {
+        let a = rng.point(-20., 20.);
+        Polygon::Triangle(a, a + rng.point(-3., 3.), a + rng.point(-3., 3.))
+    }).collect();
+    polys.push(Polygon::Plane(Plane::new(Point3::new(0., 25., 0.), Vec3::new(0., -1., 0.))));
+    polys
+}
+
+/// Rays from around the triangles towards points among them.
+fn rays(rng: &mut Rng) -> Vec<Ray3> {
+    (0..500).map(|_| {
+        let origin = rng.point(-30., 30.);
+        Ray3::new(origin, Vec3::from(rng.point(-15., 15.) - origin))
+    }).collect()
+}
+
+fn brute_force(polys: &[Polygon], ray: &Ray3) -> Option<(usize, f64)> {
+    polys.iter().enumerate()
+        .filter_map(|(i, p)| p.intersect(ray).map(|hit| (i, hit.dist)))
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+}
+
+#[test]
+fn bvh_matches_brute_force() {
+    let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+    let polys = polygons(&mut rng);
+    let bounds: Vec<_> = polys.iter().map(|p| p.bounds()).collect();
+    let bvh = Bvh::build(&bounds);
+    assert_eq!(bvh.len(), polys.len());
+
+    let mut hits = 0;
+    for ray in rays(&mut rng) {
+        let expected = brute_force(&polys, &ray);
+        let found = bvh.closest(&ray, |i| polys[i].intersect(&ray).map(|hit| (i, hit.dist)));
+        assert_eq!(found.map(|(_, d)| d), expected.map(|(_, d)| d), "{:?}", ray);
+        hits += expected.is_some() as usize;
+
+        for &max_dist in &[5., 20., 60.] {
+            let blocks = |i: usize| polys[i].intersect(&ray).is_some_and(|hit| hit.dist < max_dist);
+            assert_eq!(
+                bvh.any(&ray, max_dist, blocks),
+                expected.is_some_and(|(_, d)| d < max_dist),
+                "{:?} within {}", ray, max_dist,
+            );
+        }
+    }
+    // most rays should hit something for the comparison to mean much
+    assert!(hits > 250, "{} hits", hits);
+}
+
+#[test]
+fn object_refits_after_moving() {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    let mut obj = Object::new(Rgb(255, 255, 255), polygons(&mut rng));
+    obj.apply(&Transform::pivot(1.1, Vec3::new(1., 2., 3.).normalize(), Point3::new(5., 0., 0.)))
+       .apply(&Transform::translate(Vec3::new(-4., 3., 6.)));
+
+    for ray in rays(&mut rng) {
+        let expected = brute_force(&obj.polygons, &ray);
+        let hit = obj.intersect(&ray);
+        assert_eq!(hit.map(|h| (h.poly, h.dist)), expected, "{:?}", ray);
+        assert_eq!(obj.occludes(&ray, 20.), expected.is_some_and(|(_, d)| d < 20.));
+    }
+}
+
+#[test]
+fn scene_sees_moved_objects_before_render() {
+    let mut s = Scene::new();
+    let a = s.add(Object::new(
+        Rgb(255, 255, 255),
+        vec![Polygon::Sphere(Sphere::new(Point3::new(0., 0., -10.), 1.))],
+    ));
+    s.add(Object::new(
+        Rgb(255, 255, 255),
+        vec![Polygon::Sphere(Sphere::new(Point3::new(20., 0., -10.), 1.))],
+    ));
+    s.refit();
+
+    let ray = Ray3::new(Point3::new(10., 0., 0.), Vec3::new(0., 0., -1.));
+    assert!(s.closest_hit(&ray).is_none());
+    assert!(!s.any_hit(&ray, 100.));
+
+    s.objects_mut()[a].apply(&Transform::translate(Vec3::new(10., 0., 0.)));
+    let (_, hit) = s.closest_hit(&ray).expect("moved object missed");
+    assert_eq!(hit.object, a);
+    assert!(s.any_hit(&ray, 100.));
+
+    // and the same once the hierarchy is refitted
+    s.refit();
+    assert_eq!(s.closest_hit(&ray).map(|(_, hit)| hit.object), Some(a));
+}
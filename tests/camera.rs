@@ -0,0 +1,116 @@
+//! Camera rays through resizes and projection changes.
+
+extern crate ascworld;
+
+use ascworld::*;
+
+mod common;
+use common::*;
+
+#[test]
+fn resize_through_no_rows_keeps_framing() {
+    let mut s = scene();
+    let corners = |s: &Scene| [(0, 0), (23, 15)].map(|c| {
+        let ray = s.camera.get_ray(c).unwrap();
+        (Vec3::from(ray.origin()), ray.vec())
+    });
+    let before = corners(&s);
+    // a terminal one row tall leaves no rows under the status line
+    s.camera.resize((SIZE.0, 0), 1.);
+    assert!(s.camera.get_ray((0, 0)).is_none());
+    s.camera.resize((0, 0), 1.);
+    s.camera.resize((12, 8), 1.);
+    s.camera.resize(SIZE, 1.);
+    for (&(o, v), &(o0, v0)) in corners(&s).iter().zip(&before) {
+        assert!((o - o0).norm() < 1e-9 && (v - v0).norm() < 1e-9);
+    }
+}
+
+#[test]
+fn perspective_matches_default() {
+    let mut s = scene();
+    let default = s.camera.get_ray((5, 7)).unwrap();
+    s.camera = Camera::perspective(&matching_perspective(), SIZE).unwrap();
+    let ray = s.camera.get_ray((5, 7)).unwrap();
+    let close = |a: Vec3, b: Vec3| (a - b).norm() < 1e-9;
+    assert!(close(ray.origin().into(), default.origin().into()));
+    assert!(close(ray.vec(), default.vec()));
+}
+
+#[test]
+fn perspective_orbits_its_target() {
+    let camera = Camera::perspective(&Perspective {
+        eye: Point3::new(-20., -10., 10.),
+        target: Point3::new(8., 7., -11.),
+        ..Perspective::default()
+    }, SIZE).unwrap();
+    assert!(Vec3::from(camera.get_pivot() - Point3::new(8., 7., -11.)).norm() < 1e-9);
+}
+
+#[test]
+fn perspective_looking_along_up() {
+    let mut s = scene();
+    s.add(Object::new(
+        Rgb(200, 200, 200),
+        vec![Polygon::Plane(Plane::new(Point3::new(0., 0., 0.), Vec3::new(0., -1., 0.)))],
+    ));
+    // straight down onto the plane, the default up is along the view
+    s.camera = Camera::perspective(&Perspective {
+        eye: Point3::new(0., -10., 0.),
+        target: Point3::new(0., 0., 0.),
+        ..Perspective::default()
+    }, SIZE).unwrap();
+    s.threads = 1;
+    let mut rendered = s.empty_render();
+    s.render(&mut rendered);
+    assert!(rendered.rows().iter().flatten().all(|c| match c {
+        Cell::Hit{dist, ..} => dist.is_finite(),
+        Cell::Empty => false,
+    }));
+
+    let on_target = Perspective {
+        eye: Point3::new(0., -10., 0.),
+        target: Point3::new(0., -10., 0.),
+        ..Perspective::default()
+    };
+    assert!(Camera::perspective(&on_target, SIZE).is_err());
+    let no_view = Perspective { fov_y: 0., ..Perspective::default() };
+    assert!(Camera::perspective(&no_view, SIZE).is_err());
+}
+
+#[test]
+fn orthographic_rays_are_parallel() {
+    let mut s = scene();
+    s.camera.set_projection(Projection::Orthographic).zoom(0.8);
+    let (a, b) = (s.camera.get_ray((0, 0)).unwrap(), s.camera.get_ray((23, 15)).unwrap());
+    assert_eq!(a.vec(), Vec3::new(0., 0., -1.));
+    assert_eq!(b.vec(), a.vec());
+    let (_, _, z) = a.origin().unpack();
+    assert_eq!(z, 0.);
+}
+
+#[test]
+#[should_panic(expected = "zoom factor 0 isn't above 0")]
+fn zoom_needs_a_positive_factor() {
+    scene().camera.zoom(0.);
+}
+
+#[test]
+fn dolly_keeps_the_pivot() {
+    let close = |a: Point3, b: Point3| Vec3::from(a - b).norm() < 1e-9;
+    let (eye, target) = (Point3::new(-20., -10., 10.), Point3::new(8., 7., -11.));
+    let looking_at = Camera::perspective(&Perspective { eye, target, ..Perspective::default() }, SIZE);
+    for (mut camera, view) in [
+        (scene().camera, Vec3::new(0., 0., -1.)),
+        (looking_at.unwrap(), Vec3::from(target - eye).normalize()),
+    ] {
+        let (pivot, eye) = (camera.get_pivot(), camera.eye.get_abs());
+        camera.dolly(5.).dolly(-2.);
+        assert!(close(camera.get_pivot(), pivot));
+        assert!(close(camera.eye.get_abs(), eye + Point3::from(view * 3.)));
+
+        // and orbits still turn around it
+        Action::OrbitRight(0.5).apply_to(&mut camera);
+        assert!(close(camera.get_pivot(), pivot));
+    }
+}
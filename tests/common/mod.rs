@@ -0,0 +1,51 @@
+//! Fixtures shared by the integration tests, each test file takes the
+//! ones it needs with `mod common;`.
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use ascworld::*;
+
+pub const SIZE: (usize, usize) = (24, 16);
+
+/// An empty scene with the default camera sized to `SIZE`.
+pub fn scene() -> Scene {
+    let mut scene = Scene::new();
+    scene.camera.resize(SIZE, 1.);
+    scene
+}
+
+/// A green cube on the left and a yellow one on the right, returning their
+/// indices.
+pub fn add_two_cubes(s: &mut Scene) -> (usize, usize) {
+    (
+        s.add(Object::cube(Rgb(85, 255, 85), Point3::new(-2., 4., -14.), Point3::new(4., 10., -8.))),
+        s.add(Object::cube(Rgb(255, 255, 85), Point3::new(12., 4., -14.), Point3::new(18., 10., -8.))),
+    )
+}
+
+/// The perspective camera matching `scene()`'s, whose screen is 16 units
+/// high around (8, 8, 0) with the eye 45 units in front.
+pub fn matching_perspective() -> Perspective {
+    Perspective {
+        eye: Point3::new(8., 8., 45.),
+        target: Point3::new(8., 8., -10.),
+        fov_y: 2. * (8f64 / 45.).atan(),
+        aspect: Perspective::aspect_of(SIZE, 1.),
+        near: 45.,
+        ..Perspective::default()
+    }
+}
+
+/// Writes `data` to `file` in a temporary directory of its own for the
+/// test `name`, so tests running at the same time don't share files.
+pub fn temp_file<D: AsRef<[u8]>>(name: &str, file: &str, data: D) -> PathBuf {
+    let dir = env::temp_dir().join(format!("ascworld-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(file);
+    fs::write(&path, data).unwrap();
+    path
+}
@@ -0,0 +1,292 @@
+//! Golden image tests: small scenes are rendered and their cells compared
+//! against the snapshots in `tests/snapshots`.
+//!
+//! Run with `UPDATE_SNAPSHOTS=1` to write the current output as the new
+//! snapshots instead, then review the changes to them like any other diff.
+
+extern crate ascworld;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use ascworld::*;
+
+mod common;
+use common::*;
+
+/// One line per row of cells, `.` for empty cells and `rgb@depth` for hits
+/// with each colour channel cut down to a hex digit and the depth rounded
+/// to whole units, so rounding noise doesn't break the snapshots.
+fn snapshot(r: &RenderedScene) -> String {
+    let rows = r.rows();
+    let mut out = format!(
+        "# {}x{} cells, `.` empty, otherwise rgb@depth\n",
+        rows.first().map_or(0, |r| r.len()), rows.len(),
+    );
+    for row in rows {
+        let line: Vec<String> = row.iter().map(|cell| match cell {
+            Cell::Empty => format!("{:<7}", "."),
+            Cell::Hit{color: Rgb(r, g, b), dist, ..} => format!(
+                "{:x}{:x}{:x}@{:03}", r >> 4, g >> 4, b >> 4,
+                dist.round().clamp(0., 999.) as u32,
+            ),
+        }).collect();
+        out.push_str(line.join(" ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// Lines that differ between the snapshots, with a marker under the first
+/// cell that doesn't match.
+fn diff(expected: &str, actual: &str) -> String {
+    let mut out = String::new();
+    let (e, a): (Vec<_>, Vec<_>) = (expected.lines().collect(), actual.lines().collect());
+    for i in 0..e.len().max(a.len()) {
+        let (el, al) = (e.get(i).cloned().unwrap_or(""), a.get(i).cloned().unwrap_or(""));
+        if el == al {
+            continue;
+        }
+        let col = el.chars().zip(al.chars()).take_while(|(x, y)| x == y).count();
+        out += &format!(
+            "line {}, cell {}:\n  - {}\n  + {}\n    {}^\n",
+            i + 1, col / 8, el, al, " ".repeat(col),
+        );
+    }
+    out
+}
+
+fn assert_snapshot(name: &str, scene: &mut Scene) {
+    scene.threads = 1;
+    let mut rendered = scene.empty_render();
+    scene.render(&mut rendered);
+    let actual = snapshot(&rendered);
+
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "snapshots", name]
+        .iter().collect();
+    let path = path.with_extension("txt");
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| panic!(
+        "can't read {}: {}\nrun with UPDATE_SNAPSHOTS=1 to create it",
+        path.display(), e,
+    ));
+    if expected != actual {
+        panic!(
+            "{} doesn't match the render:\n{}\nrun with UPDATE_SNAPSHOTS=1 to \
+             accept the new output",
+            path.display(), diff(&expected, &actual),
+        );
+    }
+}
+
+/// `scene()` with the lighting most tests use: a little ambient light and
+/// a directional light shining along `dir`.
+fn lit_scene(dir: Vec3, shadows: bool) -> Scene {
+    let mut s = scene();
+    s.lights.push(Light::ambient(0.2));
+    s.lights.push(Light::directional(dir, 0.8).with_shadows(shadows));
+    s
+}
+
+/// A cube tilted towards the camera and lit from the side.
+fn tilted_cube_scene() -> Scene {
+    let mut s = lit_scene(Vec3::new(1., 0.5, -1.), false);
+    let mut c = Object::cube(Rgb(55, 155, 255), Point3::new(3., 3., -15.), Point3::new(13., 13., -5.));
+    c.apply(&Transform::pivot(0.6, Vec3::new(1., 1., 0.).normalize(), Point3::new(8., 8., -10.)));
+    s.add(c);
+    s
+}
+
+#[test]
+fn triangle_shaded_by_distance() {
+    let mut s = scene();
+    s.add(Object::new(
+        Rgb(255, 255, 255),
+        vec![Polygon::Triangle(
+            Point3::new(0., 2., -5.),
+            Point3::new(16., 2., -15.),
+            Point3::new(8., 14., -10.),
+        )],
+    ));
+    assert_snapshot("triangle_shaded_by_distance", &mut s);
+}
+
+#[test]
+fn cube_lit_from_the_side() {
+    assert_snapshot("cube_lit_from_the_side", &mut tilted_cube_scene());
+}
+
+#[test]
+fn cube_shadow_on_wall() {
+    let mut s = lit_scene(Vec3::new(0.5, 0.5, -1.), true);
+    s.add(Object::cube(Rgb(255, 85, 85), Point3::new(2., 2., -8.), Point3::new(8., 8., -2.)));
+    s.add(Object::new(
+        Rgb(200, 200, 200),
+        vec![
+            Polygon::Triangle(
+                Point3::new(-10., -10., -20.),
+                Point3::new(30., -10., -20.),
+                Point3::new(30., 30., -20.),
+            ),
+            Polygon::Triangle(
+                Point3::new(-10., -10., -20.),
+                Point3::new(30., 30., -20.),
+                Point3::new(-10., 30., -20.),
+            ),
+        ],
+    ));
+    assert_snapshot("cube_shadow_on_wall", &mut s);
+}
+
+#[test]
+fn point_light_between_objects() {
+    let mut s = scene();
+    add_two_cubes(&mut s);
+    s.lights.push(Light::ambient(0.1));
+    s.lights.push(Light::point(Point3::new(8., 7., -11.), 1.));
+    assert_snapshot("point_light_between_objects", &mut s);
+}
+
+#[test]
+fn demo_scene_file() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "scenes", "demo.scene"]
+        .iter().collect();
+    let mut s = Scene::from_file(path).unwrap();
+    s.camera.resize(SIZE, 1.);
+    assert_snapshot("demo_scene_file", &mut s);
+}
+
+#[test]
+fn sphere_and_ellipsoid() {
+    let mut s = lit_scene(Vec3::new(1., 0.5, -1.), true);
+    s.add(Object::new(
+        Rgb(255, 85, 255),
+        vec![Polygon::Sphere(Sphere::new(Point3::new(2., 8., -10.), 4.))],
+    ));
+    let mut e = Sphere::new(Point3::new(0., 0., 0.), 1.);
+    e.apply(&Transform::scale(Vec3::new(2., 5., 2.)))
+     .apply(&Transform::rotate(0.5, Vec3::new(0., 0., 1.)))
+     .apply(&Transform::translate(Vec3::new(14., 8., -10.)));
+    s.add(Object::new(Rgb(85, 255, 255), vec![Polygon::Sphere(e)]));
+    assert_snapshot("sphere_and_ellipsoid", &mut s);
+}
+
+#[test]
+fn disc_over_plane() {
+    let mut s = lit_scene(Vec3::new(0.3, 1., -0.5), true);
+    s.add(Object::new(
+        Rgb(200, 200, 200),
+        vec![Polygon::Plane(Plane::new(Point3::new(0., 14., 0.), Vec3::new(0., -1., 0.)))],
+    ));
+    let mut disc = Disc::new(Point3::new(8., 6., -12.), Vec3::new(0., 0., 1.), 5.);
+    disc.apply(&Transform::pivot(0.8, Vec3::new(1., 0., 0.), Point3::new(8., 6., -12.)));
+    s.add(Object::new(Rgb(255, 170, 0), vec![Polygon::Disc(disc)]));
+    assert_snapshot("disc_over_plane", &mut s);
+}
+
+/// A shape defined outside the crate: an axis-aligned box coloured by the
+/// axis of the face hit. Transforms move its corners, so rotations give
+/// the box around the rotated corners.
+struct Slab {
+    corners: [Point3; 2],
+    colors: [Rgb; 3],
+}
+
+impl Intersectable for Slab {
+    fn intersect(&self, ray: &Ray3) -> Option<Hit> {
+        let dist = ray.box_dist(&self.corners)?;
+        if dist == 0. {
+            return None;
+        }
+        let p = ray.origin() + Point3::from(ray.vec().normalize() * dist);
+
+        // the face hit is the one the point lies closest to
+        let (x, y, z) = p.unpack();
+        let (x0, y0, z0) = self.corners[0].unpack();
+        let (x1, y1, z1) = self.corners[1].unpack();
+        let gaps = [
+            (x - x0).abs().min((x - x1).abs()),
+            (y - y0).abs().min((y - y1).abs()),
+            (z - z0).abs().min((z - z1).abs()),
+        ];
+        let axis = (0..3).min_by(|&a, &b| gaps[a].partial_cmp(&gaps[b]).unwrap()).unwrap();
+        let mut n = [0.; 3];
+        n[axis] = 1.;
+        let hit = Hit::new(ray, p, Vec3::new(n[0], n[1], n[2]), (0., 0.));
+        Some(Hit { poly: axis, ..hit })
+    }
+}
+
+impl Shape for Slab {
+    fn bounds(&self) -> [Point3; 2] {
+        self.corners
+    }
+
+    fn transform(&mut self, t: &Transform) {
+        let (mut a, mut b) = (self.corners[0], self.corners[1]);
+        a.apply(t);
+        b.apply(t);
+        self.corners = [a.lower_bound(b), a.upper_bound(b)];
+    }
+
+    fn color_of(&self, part: usize) -> Rgb {
+        self.colors[part]
+    }
+}
+
+#[test]
+fn custom_shape_next_to_object() {
+    let mut s = lit_scene(Vec3::new(1., 1., -1.), true);
+    let slab = s.add(Slab {
+        corners: [Point3::new(-2., 3., -16.), Point3::new(6., 13., -8.)],
+        colors: [Rgb(255, 85, 85), Rgb(85, 255, 85), Rgb(85, 85, 255)],
+    });
+    s.objects_mut()[slab].apply(&Transform::translate(Vec3::new(0., 0., 2.)));
+    s.add(Object::new(
+        Rgb(255, 255, 255),
+        vec![Polygon::Sphere(Sphere::new(Point3::new(14., 8., -12.), 3.))],
+    ));
+    assert_snapshot("custom_shape_next_to_object", &mut s);
+}
+
+#[test]
+fn perspective_camera_matches_default() {
+    let mut s = tilted_cube_scene();
+    s.camera = Camera::perspective(&matching_perspective(), SIZE).unwrap();
+    assert_snapshot("cube_lit_from_the_side", &mut s);
+}
+
+#[test]
+fn perspective_look_at_with_far_plane() {
+    let mut s = lit_scene(Vec3::new(0.3, 1., 0.5), true);
+    add_two_cubes(&mut s);
+    s.add(Object::new(
+        Rgb(200, 200, 200),
+        vec![Polygon::Plane(Plane::new(Point3::new(0., 10., 0.), Vec3::new(0., -1., 0.)))],
+    ));
+    s.camera = Camera::perspective(&Perspective {
+        eye: Point3::new(-20., -10., 10.),
+        target: Point3::new(8., 7., -11.),
+        fov_y: 50f64.to_radians(),
+        aspect: Perspective::aspect_of(SIZE, 1.),
+        far: 60.,
+        ..Perspective::default()
+    }, SIZE).unwrap();
+    assert_snapshot("perspective_look_at_with_far_plane", &mut s);
+}
+
+#[test]
+fn orthographic_keeps_sizes() {
+    let mut s = lit_scene(Vec3::new(1., 0.5, -1.), false);
+    s.add(Object::cube(Rgb(85, 255, 85), Point3::new(-3., 4., -9.), Point3::new(3., 10., -3.)));
+    s.add(Object::cube(Rgb(255, 255, 85), Point3::new(13., 4., -39.), Point3::new(19., 10., -33.)));
+    s.camera.set_projection(Projection::Orthographic).zoom(0.8);
+    assert_snapshot("orthographic_keeps_sizes", &mut s);
+}
@@ -0,0 +1,44 @@
+//! The hit records intersections return.
+
+extern crate ascworld;
+
+use ascworld::*;
+
+mod common;
+use common::*;
+
+#[test]
+fn hit_records_what_was_hit() {
+    let mut s = scene();
+    s.add(Object::cube(Rgb(255, 255, 255), Point3::new(-20., -20., -30.), Point3::new(-10., -10., -20.)));
+    let tri = s.add(Object::new(
+        Rgb(255, 255, 255),
+        vec![
+            Polygon::Triangle(
+                Point3::new(100., 0., -5.),
+                Point3::new(101., 0., -5.),
+                Point3::new(100., 1., -5.),
+            ),
+            Polygon::Triangle(
+                Point3::new(0., 0., -10.),
+                Point3::new(4., 0., -10.),
+                Point3::new(0., 4., -10.),
+            ),
+        ],
+    ));
+    s.refit();
+
+    let ray = Ray3::new(Point3::new(1., 2., 0.), Vec3::new(0., 0., -1.));
+    let (_, hit) = s.closest_hit(&ray).unwrap();
+    assert_eq!((hit.object, hit.poly), (tri, 1));
+    assert_eq!(hit.point, Point3::new(1., 2., -10.));
+    assert_eq!(hit.dist, 10.);
+    assert_eq!(hit.normal, Vec3::new(0., 0., 1.));
+    assert!(hit.front);
+    assert_eq!((hit.u, hit.v), (0.25, 0.5));
+
+    let back = Ray3::new(Point3::new(1., 2., -20.), Vec3::new(0., 0., 1.));
+    let (_, hit) = s.closest_hit(&back).unwrap();
+    assert!(!hit.front);
+    assert_eq!(hit.normal, Vec3::new(0., 0., -1.));
+}
@@ -0,0 +1,77 @@
+extern crate ascworld;
+extern crate termion;
+
+use std::f64::consts::PI;
+use std::fs;
+use std::path::PathBuf;
+
+use ascworld::*;
+use termion::event::Key;
+
+mod common;
+use common::temp_file;
+
+#[test]
+fn default_keymap_is_wasd() {
+    let keys = Keymap::default();
+    assert_eq!(keys.action(Key::Char('q')), Some(Action::Quit));
+    assert_eq!(keys.action(Key::Char('w')), Some(Action::MoveForward(2.5)));
+    assert_eq!(keys.action(Key::Char('A')), Some(Action::OrbitRight(-PI / 90.)));
+    assert_eq!(keys.action(Key::Char('R')), Some(Action::Dolly(-2.5)));
+    assert_eq!(keys.action(Key::Char('F')), Some(Action::EyeDistance(-5.)));
+    assert_eq!(keys.action(Key::Char('-')), Some(Action::Zoom(0.8)));
+    assert_eq!(keys.action(Key::Char('p')), Some(Action::ToggleProjection));
+    assert_eq!(keys.action(Key::Char('x')), None);
+}
+
+#[test]
+fn dvorak_keymap() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "keymaps", "dvorak"].iter().collect();
+    let keys = Keymap::from_file(path).unwrap();
+    assert_eq!(keys.action(Key::Char(',')), Some(Action::MoveForward(2.5)));
+    assert_eq!(keys.action(Key::Char('O')), Some(Action::OrbitUp(-PI / 90.)));
+    assert_eq!(keys.action(Key::Char('p')), Some(Action::Dolly(2.5)));
+    assert_eq!(keys.action(Key::Char('\'')), Some(Action::Quit));
+    // cleared along with the rest of the defaults
+    assert_eq!(keys.action(Key::Char('w')), None);
+    assert_eq!(keys.action(Key::Char('q')), None);
+}
+
+#[test]
+fn keymap_file_overrides_defaults() {
+    let path = temp_file("keymap-overrides", "keymap", "\
+        # comment\n\
+        up      move-up 1   # trailing comment\n\
+        ctrl-x  quit\n\
+        f3      orbit-right 90\n\
+        r       dolly 10\n\
+        f       none\n");
+    let keys = Keymap::from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(keys.action(Key::Up), Some(Action::MoveUp(1.)));
+    assert_eq!(keys.action(Key::Ctrl('x')), Some(Action::Quit));
+    assert_eq!(keys.action(Key::F(3)), Some(Action::OrbitRight(PI / 2.)));
+    assert_eq!(keys.action(Key::Char('r')), Some(Action::Dolly(10.)));
+    assert_eq!(keys.action(Key::Char('f')), None);
+    assert_eq!(keys.action(Key::Char('w')), Some(Action::MoveForward(2.5)));
+}
+
+#[test]
+fn keymap_errors_name_the_line() {
+    for (src, msg) in &[
+        ("w jump", ":1: unknown action `jump`"),
+        ("\nnope quit", ":2: unknown key `nope`"),
+        ("w move-up far", ":1: expected a number, found `far`"),
+        ("q quit 1", ":1: quit takes no amount"),
+        ("p toggle-projection 2", ":1: toggle-projection takes no amount"),
+        ("z zoom 0", ":1: zoom needs a finite factor above 0"),
+        ("z zoom inf", ":1: zoom needs a finite factor above 0"),
+        ("z zoom 1e400", ":1: zoom needs a finite factor above 0"),
+        ("z zoom nan", ":1: zoom needs a finite factor above 0"),
+    ] {
+        let path = temp_file("keymap-errors", "keymap", src);
+        let err = Keymap::from_file(&path).unwrap_err().to_string();
+        fs::remove_file(&path).unwrap();
+        assert!(err.ends_with(msg), "{}", err);
+    }
+}
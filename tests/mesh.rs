@@ -0,0 +1,212 @@
+//! Loading meshes from files, and the errors for broken ones.
+
+extern crate ascworld;
+
+use std::fs;
+
+use ascworld::*;
+
+mod common;
+use common::temp_file;
+
+fn triangles(obj: &Object) -> Vec<(Point3, Point3, Point3)> {
+    obj.polygons.iter().map(|p| match *p {
+        Polygon::Triangle(a, b, c) => (a, b, c),
+        _ => panic!("not a triangle"),
+    }).collect()
+}
+
+const ASCII_STL: &str = "\
+solid two
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 1 0
+    endloop
+  endfacet
+  facet normal 0 0 0
+    outer loop
+      vertex 0 0 1
+      vertex 1 0 1
+      vertex 0 1 1.5
+    endloop
+  endfacet
+endsolid two
+";
+
+/// A binary STL of `facets`, with a header starting with `header`.
+fn binary_stl(header: &[u8], facets: &[[f32; 9]]) -> Vec<u8> {
+    let mut data = header.to_vec();
+    data.resize(80, b' ');
+    data.extend_from_slice(&(facets.len() as u32).to_le_bytes());
+    for facet in facets {
+        data.extend_from_slice(&[0; 12]);
+        facet.iter().for_each(|x| data.extend_from_slice(&x.to_le_bytes()));
+        data.extend_from_slice(&[0; 2]);
+    }
+    data
+}
+
+const FACETS: [[f32; 9]; 2] = [
+    [0., 0., 0., 1., 0., 0., 0., 1., 0.],
+    [0., 0., 1., 1., 0., 1., 0., 1., 1.5],
+];
+
+fn expected_facets() -> Vec<(Point3, Point3, Point3)> {
+    FACETS.iter().map(|f| (
+        Point3::new(f[0] as f64, f[1] as f64, f[2] as f64),
+        Point3::new(f[3] as f64, f[4] as f64, f[5] as f64),
+        Point3::new(f[6] as f64, f[7] as f64, f[8] as f64),
+    )).collect()
+}
+
+#[test]
+fn stl_ascii() {
+    let path = temp_file("stl-ascii", "two.stl", ASCII_STL.as_bytes());
+    let obj = Object::from_stl(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(triangles(&obj), expected_facets());
+}
+
+#[test]
+fn stl_binary() {
+    // binary headers may start with `solid` too, and files may be padded
+    for (file, header, pad) in &[
+        ("plain.stl", &b"exported"[..], 0),
+        ("solid.stl", &b"solid but binary"[..], 0),
+        ("pad.stl", &b"solid padded"[..], 130),
+    ] {
+        let mut data = binary_stl(header, &FACETS);
+        data.resize(data.len() + pad, 0);
+        let path = temp_file("stl-binary", file, &data);
+        let obj = Object::from_stl(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(triangles(&obj), expected_facets(), "{}", file);
+    }
+}
+
+#[test]
+fn stl_errors() {
+    let mut short = binary_stl(b"cut", &FACETS);
+    short.truncate(short.len() - 10);
+    let mut latin1 = b"solid x\n  facet normal 0 0 1\n".to_vec();
+    latin1.extend_from_slice(b"    outer loop \xe9\n");
+    for (file, data, msg) in &[
+        ("short.stl", short, ".stl: binary STL with 2 facets is cut short at 174 bytes"),
+        ("tiny.stl", b"\0\0\0".to_vec(), ".stl: binary STL needs at least 84 bytes, found 3"),
+        ("latin1.stl", latin1, ":3: neither a binary STL nor UTF-8 text"),
+        ("word.stl", b"solid x\n  facet\n  verts 1 2 3\n".to_vec(), ":3: unexpected `verts`"),
+        ("coords.stl", b"solid x\nfacet\nvertex 1 2\n".to_vec(),
+            ":3: expected 3 numbers, found 2"),
+        ("number.stl", b"solid x\nfacet\nvertex 1 2 z\n".to_vec(),
+            ":3: expected a number, found `z`"),
+        ("outside.stl", b"solid x\nvertex 1 2 3\n".to_vec(), ":2: vertex outside of a facet"),
+        ("open.stl", b"solid x\nfacet\nvertex 1 2 3\n".to_vec(), ":3: unterminated facet"),
+        ("two.stl", b"solid x\nfacet\nvertex 1 2 3\nendfacet\n".to_vec(),
+            ":4: facet needs 3 vertices, found 1"),
+    ] {
+        let path = temp_file("stl-errors", file, data);
+        let err = match Object::from_stl(&path) {
+            Err(e) => e.to_string(),
+            Ok(_) => panic!("{} loaded", file),
+        };
+        fs::remove_file(&path).unwrap();
+        assert!(err.starts_with(&path.display().to_string()), "{}", err);
+        assert!(err.ends_with(msg), "{}", err);
+    }
+}
+
+/// A square of two triangles with vertex normals, each triangle with its
+/// own material.
+const SQUARE_OBJ: &str = "\
+# square
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vn 0 0 1
+usemtl red
+f 1//1 2//1 3//1
+usemtl blue
+f 1//1 3//1 4//1
+";
+
+#[test]
+fn obj_with_materials() {
+    let mtl = "newmtl red\nKd 1 0 0\nnewmtl blue\nKd 0 0 1\n";
+    let mtl_path = temp_file("obj-materials", "square.mtl", mtl.as_bytes());
+    let path = temp_file("obj-materials", "square.obj", SQUARE_OBJ.as_bytes());
+    let obj = Object::from_obj(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&mtl_path).unwrap();
+
+    assert_eq!(obj.polygons.len(), 2);
+    assert_eq!(obj.colors, vec![Rgb(255, 0, 0), Rgb(0, 0, 255)]);
+    assert_eq!(obj.normals, vec![Vec3::new(0., 0., 1.); 2]);
+}
+
+#[test]
+fn obj_without_material_file() {
+    let path = temp_file("obj-no-mtl", "square.obj", SQUARE_OBJ.as_bytes());
+    let obj = Object::from_obj(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(obj.polygons.len(), 2);
+    assert_eq!(obj.color_of(0), obj.color_of(1));
+}
+
+#[test]
+fn obj_errors() {
+    // faces after these refer to the vertices and normal of a triangle
+    let tri = "v 0 0 0\nv 1 0 0\nv 1 1 0\nvn 0 0 1\n";
+    for (after_tri, src, msg) in &[
+        (false, "v 1 2\n", ":1: expected 3 numbers, found 2"),
+        (false, "v 0 0 0\nv 1 x 0\n", ":2: expected a number, found `x`"),
+        (false, "vn 1\n", ":1: expected 3 numbers, found 1"),
+        (false, "f 1 2 3\n", ":1: vertex index 1 out of range (0 defined)"),
+        (true, "f 1 2\n", ":5: face needs at least 3 vertices, found 2"),
+        (true, "f 1 2 a\n", ":5: invalid vertex index `a`"),
+        (true, "f 1/1/1/1 2 3\n", ":5: malformed face element `1/1/1/1`"),
+        (true, "f 1 2 4\n", ":5: vertex index 4 out of range (3 defined)"),
+        (true, "f 0 1 2\n", ":5: vertex index 0 out of range (3 defined)"),
+        (true, "f -4 -2 -1\n", ":5: vertex index -4 out of range (3 defined)"),
+        (true, "f 1//1 2//2 3//1\n", ":5: normal index 2 out of range (1 defined)"),
+        (true, "usemtl\n", ":5: usemtl without a name"),
+    ] {
+        let src = if *after_tri { format!("{}{}", tri, src) } else { src.to_string() };
+        let path = temp_file("obj-errors", "bad.obj", src.as_bytes());
+        let err = match Object::from_obj(&path) {
+            Err(e) => e.to_string(),
+            Ok(_) => panic!("{:?} loaded", src),
+        };
+        fs::remove_file(&path).unwrap();
+        assert!(err.starts_with(&path.display().to_string()), "{}", err);
+        assert!(err.ends_with(msg), "{}", err);
+    }
+}
+
+#[test]
+fn obj_skips_faces_without_area() {
+    // the middle triangle of the fan and the last face are lines
+    let src = "v 0 0 0\nv 1 0 0\nv 2 0 0\nv 2 1 0\nvn 0 0 1\n\
+        f 1//1 2//1 4//1\nf 1 2 3 4\nf 1 2 3\n";
+    let path = temp_file("obj-no-area", "flat.obj", src.as_bytes());
+    let obj = Object::from_obj(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(obj.polygons.len(), 2);
+    assert_eq!(obj.normals.len(), 2);
+    assert!(obj.normals.iter().all(|n| n.norm().is_finite()), "{:?}", obj.normals);
+}
+
+#[test]
+fn stretched_mesh_keeps_normals_on_the_surface() {
+    let mut obj = Object::new(Rgb(255, 255, 255), vec![Polygon::Triangle(
+        Point3::new(1., 0., 0.), Point3::new(0., 1., 0.), Point3::new(0., 0., -1.),
+    )]);
+    obj.normals = vec![obj.polygons[0].normal().unwrap()];
+    obj.apply(&Transform::scale(Vec3::new(4., 1., 0.5)));
+
+    let expected = obj.polygons[0].normal().unwrap();
+    let n = obj.normals[0];
+    assert!((n - expected).norm() < 1e-9, "{:?} against {:?}", n, expected);
+}
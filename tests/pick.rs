@@ -0,0 +1,57 @@
+//! Picking objects by screen position and highlighting them.
+
+extern crate ascworld;
+
+use ascworld::*;
+
+mod common;
+use common::*;
+
+#[test]
+fn picks_match_the_render() {
+    let mut s = scene();
+    let (a, b) = add_two_cubes(&mut s);
+    let mut rendered = s.empty_render();
+    s.render(&mut rendered);
+
+    let mut seen = vec![];
+    for (y, row) in rendered.rows().iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let pick = rendered.pick((x, y));
+            assert_eq!(pick, s.pick((x, y)));
+            assert_eq!(pick.is_some(), matches!(cell, Cell::Hit{..}));
+            seen.extend(pick.map(|p| p.object));
+        }
+    }
+    assert!(seen.contains(&a) && seen.contains(&b));
+    assert_eq!(rendered.pick((SIZE.0, 0)), None);
+
+    // a half block covers two rows of cells, any hit in either counts
+    for y in 0..SIZE.1 / 2 {
+        for x in 0..SIZE.0 {
+            let (top, bottom) = (rendered.pick((x, 2 * y)), rendered.pick((x, 2 * y + 1)));
+            let expected = match (top, bottom) {
+                (Some(t), Some(b)) => Some(if b.dist < t.dist { b } else { t }),
+                (t, b) => t.or(b),
+            };
+            assert_eq!(rendered.pick_glyph(&Style::HalfBlock, (x, y)), expected);
+        }
+    }
+}
+
+#[test]
+fn selection_highlights_object() {
+    let mut s = scene();
+    let (_, b) = add_two_cubes(&mut s);
+    let mut rendered = s.empty_render();
+    s.render(&mut rendered);
+
+    let before = rendered.rows().to_vec();
+    rendered.highlight(b, Rgb(255, 255, 255));
+    for (y, (old, new)) in before.iter().zip(rendered.rows()).enumerate() {
+        for (x, (old, new)) in old.iter().zip(new).enumerate() {
+            let picked_b = rendered.pick((x, y)).is_some_and(|p| p.object == b);
+            assert_eq!(old != new, picked_b, "cell {} {}", x, y);
+        }
+    }
+}
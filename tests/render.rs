@@ -0,0 +1,33 @@
+//! Rendering on a pool of worker threads.
+
+extern crate ascworld;
+
+use std::path::PathBuf;
+
+use ascworld::*;
+
+mod common;
+use common::*;
+
+#[test]
+fn threads_render_the_same_cells() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "scenes", "demo.scene"]
+        .iter().collect();
+    let mut s = Scene::from_file(path).unwrap();
+    s.camera.resize(SIZE, 1.);
+
+    s.threads = 1;
+    let mut single = s.empty_render();
+    s.render(&mut single);
+    for threads in [2, 3, 8] {
+        s.threads = threads;
+        let mut multi = s.empty_render();
+        s.render(&mut multi);
+        assert_eq!(single.rows(), multi.rows(), "{} threads", threads);
+        for y in 0..SIZE.1 {
+            for x in 0..SIZE.0 {
+                assert_eq!(single.pick((x, y)), multi.pick((x, y)), "{} threads", threads);
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Reading scene files, and the errors for broken ones.
+
+extern crate ascworld;
+
+use std::fs;
+
+use ascworld::*;
+
+mod common;
+use common::temp_file;
+
+#[test]
+fn scene_file_with_mesh() {
+    let mesh = temp_file("scene-mesh", "tri.obj", "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n");
+    let path = temp_file("scene-mesh", "tri.scene", "\
+        light directional 0 1 0 0.5 shadows\n\
+        object  # mesh next to the scene file\n\
+            mesh tri.obj\n\
+            triangle 0 0 -1  1 0 -1  0 1 -1\n\
+            translate 0 0 -5\n\
+        end\n");
+    let s = Scene::from_file(&path).unwrap();
+    assert_eq!(scene_file::mesh_files(&path), vec![mesh.clone()]);
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&mesh).unwrap();
+
+    assert_eq!(s.objects().len(), 1);
+    assert_eq!(s.lights.len(), 1);
+    assert!(s.lights[0].shadows);
+    // the mesh comes first, both take the default colour
+    let [lo, hi] = s.objects()[0].bounds();
+    assert_eq!((lo, hi), (Point3::new(0., 0., -6.), Point3::new(1., 1., -5.)));
+    assert_eq!(s.objects()[0].color_of(0), s.objects()[0].color_of(1));
+}
+
+#[test]
+fn scene_file_errors() {
+    for (src, msg) in &[
+        ("camera\n", ":1: camera needs a statement"),
+        ("camera fly\n", ":1: unknown camera statement `fly`"),
+        ("camera zoom 0\n", ":1: zoom needs a finite factor above 0"),
+        ("camera zoom inf\n", ":1: zoom needs a finite factor above 0"),
+        ("camera zoom nan\n", ":1: zoom needs a finite factor above 0"),
+        ("camera rotate 10 0 0 0\n", ":1: rotation axis can't be zero"),
+        ("camera translate 1 2\n", ":1: expected 3 numbers, found 2"),
+        ("light\n", ":1: light needs a kind"),
+        ("light spot 1\n", ":1: unknown light kind `spot`"),
+        ("light directional 0 0 0 1\n", ":1: light direction can't be zero"),
+        ("light ambient 0.2 shadows 1\n", ":1: unexpected `1`"),
+        ("\nlight point 1 2 x 1\n", ":2: expected a number, found `x`"),
+        ("shadow_bias 0.1 0.2\n", ":1: unexpected `0.2`"),
+        ("end\n", ":1: `end` without `object`"),
+        ("sky blue\n", ":1: unknown statement `sky`"),
+        ("object\n  color 1 2 3\n", ":1: object is missing its `end`"),
+        ("object\n  wobble\nend\n", ":2: unknown object statement `wobble`"),
+        ("object\n  sphere 0 0 0 -1\nend\n", ":2: sphere radius must be positive"),
+        ("object\n  disc 0 0 0  0 0 0  1\nend\n", ":2: disc needs a normal and a positive radius"),
+        ("object\n  plane 0 0 0  0 0 0\nend\n", ":2: plane normal can't be zero"),
+        ("object\n  rotate 10 0 1 0  1 2\nend\n", ":2: expected 0 or 3 centre coordinates, found 2"),
+        ("object\n  mesh\nend\n", ":2: mesh needs a file name"),
+        ("object\n  mesh model.ply\nend\n", "model.ply`"),
+    ] {
+        let path = temp_file("scene-errors", "bad.scene", src);
+        let err = match Scene::from_file(&path) {
+            Err(e) => e.to_string(),
+            Ok(_) => panic!("{:?} loaded", src),
+        };
+        fs::remove_file(&path).unwrap();
+        assert!(err.starts_with(&path.display().to_string()), "{}", err);
+        assert!(err.ends_with(msg), "{}", err);
+    }
+}
+
+#[test]
+fn scene_file_mesh_errors_name_the_mesh() {
+    let mesh = temp_file("scene-mesh-errors", "bad.obj", "v 0 0\n");
+    let path = temp_file("scene-mesh-errors", "bad.scene", "object\n  mesh bad.obj\nend\n");
+    let err = match Scene::from_file(&path) {
+        Err(e) => e.to_string(),
+        Ok(_) => panic!("loaded"),
+    };
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&mesh).unwrap();
+    assert_eq!(err, format!("{}:1: expected 3 numbers, found 2", mesh.display()));
+}